@@ -1,7 +1,6 @@
 #[macro_use]
 extern crate log;
 
-use futures::executor::block_on;
 use rsocket_rust::prelude::*;
 use rsocket_rust::utils::EchoRSocket;
 use rsocket_rust::Result;
@@ -13,17 +12,17 @@ async fn main() -> Result<()> {
 
     RSocketFactory::receive()
         .transport(TcpServerTransport::from("127.0.0.1:7979"))
-        .acceptor(Box::new(|setup, _sending_socket| {
+        .acceptor_async(Box::new(|setup, _sending_socket| {
             info!("incoming socket: setup={:?}", setup);
-            Ok(Box::new(block_on(async move {
-                RSocketFactory::connect()
+            Box::pin(async move {
+                let upstream = RSocketFactory::connect()
                     .transport(TcpClientTransport::from("127.0.0.1:7878"))
                     .acceptor(Box::new(|| Box::new(EchoRSocket)))
                     .setup(Payload::from("I'm Rust!"))
                     .start()
-                    .await
-                    .unwrap()
-            })))
+                    .await?;
+                Ok(Box::new(upstream) as Box<dyn RSocket>)
+            })
         }))
         .serve()
         .await
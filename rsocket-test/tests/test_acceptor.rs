@@ -0,0 +1,102 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use rsocket_rust::error::RSocketError;
+use rsocket_rust::prelude::*;
+use rsocket_rust::utils::EchoRSocket;
+use rsocket_rust_transport_tcp::{TcpClientTransport, TcpServerTransport};
+
+fn init() {
+    let _ = env_logger::builder()
+        .format_timestamp_millis()
+        .is_test(true)
+        .try_init();
+}
+
+/// Serves with an acceptor which takes `delay` to accept clients sending the "secret" token.
+async fn serve(addr: &'static str, delay: Duration, setup_timeout: Duration) {
+    tokio::spawn(async move {
+        RSocketFactory::receive()
+            .transport(TcpServerTransport::from(addr))
+            .setup_timeout(setup_timeout)
+            .acceptor_async(Box::new(move |setup, _socket| {
+                Box::pin(async move {
+                    tokio::time::sleep(delay).await;
+                    if setup.data().map(|it| &it[..]) != Some(&b"secret"[..]) {
+                        return Err(RSocketError::RejectedSetup("bad token".into()).into());
+                    }
+                    Ok(Box::new(EchoRSocket) as Box<dyn RSocket>)
+                })
+            }))
+            .serve()
+            .await
+    });
+    tokio::time::sleep(Duration::from_millis(300)).await;
+}
+
+#[tokio::main]
+#[test]
+async fn test_async_acceptor() {
+    init();
+    serve(
+        "127.0.0.1:8014",
+        Duration::from_millis(200),
+        Duration::from_secs(5),
+    )
+    .await;
+
+    // the request goes out before the acceptor resolves, it waits for it.
+    let cli = RSocketFactory::connect()
+        .transport(TcpClientTransport::from("127.0.0.1:8014"))
+        .setup(Payload::from("secret"))
+        .start()
+        .await
+        .unwrap();
+    let res = cli.request_response(Payload::from("hello")).await.unwrap();
+    assert_eq!(Some("hello"), res.unwrap().data_utf8());
+
+    let closed = Arc::new(AtomicBool::new(false));
+    let flag = closed.clone();
+    let cli = RSocketFactory::connect()
+        .transport(TcpClientTransport::from("127.0.0.1:8014"))
+        .setup(Payload::from("guess"))
+        .on_close(Box::new(move || flag.store(true, Ordering::SeqCst)))
+        .start()
+        .await
+        .unwrap();
+    assert!(cli.request_response(Payload::from("hello")).await.is_err());
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(closed.load(Ordering::SeqCst));
+}
+
+#[tokio::main]
+#[test]
+async fn test_setup_timeout() {
+    init();
+    serve(
+        "127.0.0.1:8015",
+        Duration::from_secs(5),
+        Duration::from_millis(100),
+    )
+    .await;
+
+    let closed = Arc::new(AtomicBool::new(false));
+    let flag = closed.clone();
+    let cli = RSocketFactory::connect()
+        .transport(TcpClientTransport::from("127.0.0.1:8015"))
+        .setup(Payload::from("secret"))
+        .on_close(Box::new(move || flag.store(true, Ordering::SeqCst)))
+        .start()
+        .await
+        .unwrap();
+    let res = tokio::time::timeout(
+        Duration::from_secs(1),
+        cli.request_response(Payload::from("hello")),
+    )
+    .await
+    .expect("the connection should be rejected");
+    assert!(res.is_err());
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(closed.load(Ordering::SeqCst));
+}
@@ -0,0 +1,185 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures::stream;
+use rsocket_rust::prelude::*;
+use rsocket_rust::{async_trait, stream, Client, Result};
+use rsocket_rust_transport_tcp::{TcpClientTransport, TcpServerTransport};
+
+fn init() {
+    let _ = env_logger::builder()
+        .format_timestamp_millis()
+        .is_test(true)
+        .try_init();
+}
+
+#[derive(Clone, Copy)]
+enum Mode {
+    /// Echoes the inputs, then sends "done" once the requester has completed.
+    Echo,
+    /// Records the inputs and never sends anything.
+    Record,
+    /// Takes the first input only, then sends a few payloads of its own.
+    TakeOne,
+}
+
+#[derive(Clone)]
+struct ChannelRSocket {
+    mode: Mode,
+    received: Arc<Mutex<Vec<String>>>,
+}
+
+#[async_trait]
+impl RSocket for ChannelRSocket {
+    async fn metadata_push(&self, _req: Payload) -> Result<()> {
+        Ok(())
+    }
+
+    async fn fire_and_forget(&self, _req: Payload) -> Result<()> {
+        Ok(())
+    }
+
+    async fn request_response(&self, req: Payload) -> Result<Option<Payload>> {
+        Ok(Some(req))
+    }
+
+    fn request_stream(&self, _req: Payload) -> Flux<Result<Payload>> {
+        Box::pin(futures::stream::empty())
+    }
+
+    fn request_channel(&self, mut reqs: Flux<Result<Payload>>) -> Flux<Result<Payload>> {
+        match self.mode {
+            Mode::Echo => Box::pin(stream! {
+                while let Some(it) = reqs.next().await {
+                    yield it;
+                }
+                yield Ok(Payload::from("done"));
+            }),
+            Mode::Record => {
+                let received = self.received.clone();
+                tokio::spawn(async move {
+                    while let Some(it) = reqs.next().await {
+                        let it = match it {
+                            Ok(it) => it.data_utf8().unwrap_or_default().to_string(),
+                            Err(e) => format!("error: {}", e),
+                        };
+                        received.lock().unwrap().push(it);
+                    }
+                });
+                Box::pin(stream::pending())
+            }
+            Mode::TakeOne => Box::pin(stream! {
+                let _first = reqs.next().await;
+                drop(reqs);
+                for i in 0..5 {
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    yield Ok(Payload::builder().set_data_utf8(&i.to_string()).build());
+                }
+            }),
+        }
+    }
+}
+
+async fn connect(addr: &'static str, responder: ChannelRSocket) -> Client {
+    tokio::spawn(async move {
+        RSocketFactory::receive()
+            .transport(TcpServerTransport::from(addr))
+            .acceptor(Box::new(move |_setup, _socket| {
+                Ok(Box::new(responder.clone()))
+            }))
+            .serve()
+            .await
+    });
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    RSocketFactory::connect()
+        .transport(TcpClientTransport::from(addr))
+        .start()
+        .await
+        .unwrap()
+}
+
+fn responder(mode: Mode) -> ChannelRSocket {
+    ChannelRSocket {
+        mode,
+        received: Arc::new(Mutex::new(vec![])),
+    }
+}
+
+#[tokio::main]
+#[test]
+async fn test_half_closed_by_requester() {
+    init();
+    let cli = connect("127.0.0.1:8002", responder(Mode::Echo)).await;
+
+    let inputs: Vec<Result<Payload>> = vec![Ok(Payload::from("a")), Ok(Payload::from("b"))];
+    let outputs: Vec<String> = cli
+        .request_channel(Box::pin(stream::iter(inputs)))
+        .map(|it| it.unwrap().data_utf8().unwrap().to_string())
+        .collect()
+        .await;
+    assert_eq!(vec!["a", "b", "done"], outputs);
+
+    // a single payload completes the requester side along with the request.
+    let inputs: Vec<Result<Payload>> = vec![Ok(Payload::from("a"))];
+    let outputs: Vec<String> = cli
+        .request_channel(Box::pin(stream::iter(inputs)))
+        .map(|it| it.unwrap().data_utf8().unwrap().to_string())
+        .collect()
+        .await;
+    assert_eq!(vec!["a", "done"], outputs);
+    assert!(cli.active_streams().is_empty());
+}
+
+#[tokio::main]
+#[test]
+async fn test_error_from_requester() {
+    init();
+    let responder = responder(Mode::Record);
+    let cli = connect("127.0.0.1:8003", responder.clone()).await;
+
+    let inputs: Vec<Result<Payload>> = vec![Ok(Payload::from("a")), Err(anyhow::anyhow!("boom"))];
+    let mut outputs = cli.request_channel(Box::pin(stream::iter(inputs)));
+    assert!(outputs.next().await.is_none());
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let received = responder.received.lock().unwrap().clone();
+    assert_eq!(2, received.len());
+    assert_eq!("a", received[0]);
+    assert!(received[1].contains("boom"), "unexpected {}", received[1]);
+    assert!(cli.active_streams().is_empty());
+}
+
+#[tokio::main]
+#[test]
+async fn test_cancelled_by_responder() {
+    init();
+    let cli = connect("127.0.0.1:8004", responder(Mode::TakeOne)).await;
+
+    let produced = Arc::new(AtomicUsize::new(0));
+    let counter = produced.clone();
+    let inputs: Flux<Result<Payload>> = Box::pin(stream! {
+        loop {
+            counter.fetch_add(1, Ordering::SeqCst);
+            yield Ok(Payload::from("tick"));
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    });
+    // the responder keeps sending after it stopped consuming our payloads.
+    let outputs: Vec<String> = cli
+        .request_channel(inputs)
+        .map(|it| it.unwrap().data_utf8().unwrap().to_string())
+        .collect()
+        .await;
+    assert_eq!(vec!["0", "1", "2", "3", "4"], outputs);
+
+    let count = produced.load(Ordering::SeqCst);
+    assert!(
+        count < 5,
+        "producer should be cancelled, produced {}",
+        count
+    );
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(count, produced.load(Ordering::SeqCst));
+    assert!(cli.active_streams().is_empty());
+}
@@ -0,0 +1,125 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use rsocket_rust::prelude::*;
+use rsocket_rust::{async_trait, Result};
+use rsocket_rust_transport_tcp::{TcpClientTransport, TcpServerTransport};
+use rsocket_rust_transport_websocket::{WebsocketClientTransport, WebsocketServerTransport};
+
+fn init() {
+    let _ = env_logger::builder()
+        .format_timestamp_millis()
+        .is_test(true)
+        .try_init();
+}
+
+type Seen = Arc<Mutex<Vec<ConnectionInfo>>>;
+
+/// Responder recording what it knows about the peer of every request.
+struct InfoRSocket {
+    seen: Seen,
+}
+
+#[async_trait]
+impl RSocket for InfoRSocket {
+    async fn metadata_push(&self, _req: Payload) -> Result<()> {
+        Ok(())
+    }
+
+    async fn fire_and_forget(&self, _req: Payload) -> Result<()> {
+        Ok(())
+    }
+
+    async fn request_response(&self, req: Payload) -> Result<Option<Payload>> {
+        let ctx = RequestContext::current().unwrap();
+        self.seen
+            .lock()
+            .unwrap()
+            .push(ctx.connection_info().unwrap().clone());
+        Ok(Some(req))
+    }
+
+    fn request_stream(&self, _req: Payload) -> Flux<Result<Payload>> {
+        Box::pin(futures::stream::empty())
+    }
+
+    fn request_channel(&self, reqs: Flux<Result<Payload>>) -> Flux<Result<Payload>> {
+        reqs
+    }
+}
+
+/// Acceptor recording the peer of every connection.
+fn acceptor(seen: Seen) -> ServerResponder {
+    Box::new(move |setup, _socket| {
+        let info = setup.connection_info().expect("no connection info");
+        seen.lock().unwrap().push(info.clone());
+        Ok(Box::new(InfoRSocket { seen: seen.clone() }))
+    })
+}
+
+#[tokio::main]
+#[test]
+async fn test_tcp_connection_info() {
+    init();
+    let seen = Seen::default();
+    let accepting = acceptor(seen.clone());
+    tokio::spawn(async move {
+        RSocketFactory::receive()
+            .transport(TcpServerTransport::from("127.0.0.1:8016"))
+            .acceptor(accepting)
+            .serve()
+            .await
+    });
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    let cli = RSocketFactory::connect()
+        .transport(TcpClientTransport::from("127.0.0.1:8016"))
+        .start()
+        .await
+        .unwrap();
+    cli.request_response(Payload::from("hello")).await.unwrap();
+
+    let seen = seen.lock().unwrap().clone();
+    assert_eq!(2, seen.len());
+    for info in seen.iter() {
+        assert_eq!(TransportKind::Tcp, info.kind());
+        assert_eq!("127.0.0.1:8016", info.local_addr().unwrap().to_string());
+        let remote = info.remote_addr().unwrap();
+        assert!(remote.ip().is_loopback());
+        assert_ne!(8016, remote.port());
+        assert!(info.identity().is_none());
+    }
+}
+
+#[tokio::main]
+#[test]
+async fn test_websocket_connection_info() {
+    init();
+    let seen = Seen::default();
+    let accepting = acceptor(seen.clone());
+    tokio::spawn(async move {
+        RSocketFactory::receive()
+            .transport(WebsocketServerTransport::from("127.0.0.1:8017"))
+            .acceptor(accepting)
+            .serve()
+            .await
+    });
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    let cli = RSocketFactory::connect()
+        .transport(WebsocketClientTransport::from(
+            "ws://127.0.0.1:8017/rsocket",
+        ))
+        .start()
+        .await
+        .unwrap();
+    cli.request_response(Payload::from("hello")).await.unwrap();
+
+    let seen = seen.lock().unwrap().clone();
+    assert_eq!(2, seen.len());
+    let info = &seen[0];
+    assert_eq!(TransportKind::WebSocket, info.kind());
+    assert!(info.remote_addr().unwrap().ip().is_loopback());
+    assert_eq!(Some("/rsocket"), info.attribute("path"));
+    assert_eq!(Some("127.0.0.1:8017"), info.attribute("header.host"));
+}
@@ -0,0 +1,152 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures::stream;
+use rsocket_rust::prelude::*;
+use rsocket_rust::{async_trait, Client, Result};
+use rsocket_rust_transport_tcp::{TcpClientTransport, TcpServerTransport};
+
+fn init() {
+    let _ = env_logger::builder()
+        .format_timestamp_millis()
+        .is_test(true)
+        .try_init();
+}
+
+/// Responder recording the context of every request it handles.
+#[derive(Clone, Default)]
+struct ContextRSocket {
+    seen: Arc<Mutex<Vec<RequestContext>>>,
+    cancelled: Arc<Mutex<Vec<u32>>>,
+}
+
+impl ContextRSocket {
+    fn record(&self) {
+        let ctx = RequestContext::current().expect("no context for the request");
+        self.seen.lock().unwrap().push(ctx.clone());
+        let cancelled = self.cancelled.clone();
+        tokio::spawn(async move {
+            ctx.cancelled().await;
+            cancelled.lock().unwrap().push(ctx.stream_id());
+        });
+    }
+}
+
+#[async_trait]
+impl RSocket for ContextRSocket {
+    async fn metadata_push(&self, _req: Payload) -> Result<()> {
+        self.record();
+        Ok(())
+    }
+
+    async fn fire_and_forget(&self, _req: Payload) -> Result<()> {
+        self.record();
+        Ok(())
+    }
+
+    async fn request_response(&self, req: Payload) -> Result<Option<Payload>> {
+        self.record();
+        if req.data_utf8() == Some("slow") {
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+        Ok(Some(req))
+    }
+
+    fn request_stream(&self, req: Payload) -> Flux<Result<Payload>> {
+        self.record();
+        Box::pin(stream::once(async move { Ok(req) }).chain(stream::pending()))
+    }
+
+    fn request_channel(&self, reqs: Flux<Result<Payload>>) -> Flux<Result<Payload>> {
+        self.record();
+        reqs
+    }
+}
+
+async fn connect(addr: &'static str, responder: ContextRSocket) -> Client {
+    tokio::spawn(async move {
+        RSocketFactory::receive()
+            .transport(TcpServerTransport::from(addr))
+            .acceptor(Box::new(move |_setup, _socket| {
+                Ok(Box::new(responder.clone()))
+            }))
+            .serve()
+            .await
+    });
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    RSocketFactory::connect()
+        .transport(TcpClientTransport::from(addr))
+        .data_mime_type("application/json")
+        .metadata_mime_type("text/plain")
+        .start()
+        .await
+        .unwrap()
+}
+
+#[tokio::main]
+#[test]
+async fn test_request_context() {
+    init();
+    let responder = ContextRSocket::default();
+    let cli = connect("127.0.0.1:8010", responder.clone()).await;
+
+    assert!(RequestContext::current().is_none());
+    cli.request_response(Payload::from("hello")).await.unwrap();
+    cli.fire_and_forget(Payload::from("hello")).await.unwrap();
+    cli.metadata_push(Payload::builder().set_metadata_utf8("hello").build())
+        .await
+        .unwrap();
+    let inputs: Flux<Result<Payload>> = Box::pin(stream::iter(vec![Ok(Payload::from("hello"))]));
+    let outputs: Vec<_> = cli.request_channel(inputs).collect().await;
+    assert_eq!(1, outputs.len());
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // fire_and_forget and metadata_push are handled asynchronously, in no particular order.
+    let seen = responder.seen.lock().unwrap().clone();
+    let find = |interaction| {
+        seen.iter()
+            .find(|it| it.interaction() == interaction)
+            .unwrap_or_else(|| panic!("no {:?} handled", interaction))
+    };
+    assert_eq!(4, seen.len());
+    assert_eq!(1, find(InteractionType::RequestResponse).stream_id());
+    assert_eq!(3, find(InteractionType::FireAndForget).stream_id());
+    assert_eq!(0, find(InteractionType::MetadataPush).stream_id());
+    assert_eq!(5, find(InteractionType::RequestChannel).stream_id());
+    for ctx in seen.iter() {
+        assert_eq!(seen[0].connection_id(), ctx.connection_id());
+        assert_eq!(Some("application/json"), ctx.data_mime_type());
+        assert_eq!(Some("text/plain"), ctx.metadata_mime_type());
+        assert!(ctx.deadline().is_none());
+        assert!(!ctx.is_cancelled());
+    }
+    assert!(responder.cancelled.lock().unwrap().is_empty());
+}
+
+#[tokio::main]
+#[test]
+async fn test_request_context_cancelled() {
+    init();
+    let responder = ContextRSocket::default();
+    let cli = connect("127.0.0.1:8011", responder.clone()).await;
+
+    let mut results = cli.request_stream(Payload::from("hello"));
+    assert!(results.next().await.unwrap().is_ok());
+    drop(results);
+
+    let res = tokio::time::timeout(
+        Duration::from_millis(100),
+        cli.request_response(Payload::from("slow")),
+    )
+    .await;
+    assert!(res.is_err());
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let seen = responder.seen.lock().unwrap().clone();
+    assert_eq!(2, seen.len());
+    assert_eq!(InteractionType::RequestStream, seen[0].interaction());
+    assert!(seen.iter().all(|it| it.is_cancelled()));
+    let mut cancelled = responder.cancelled.lock().unwrap().clone();
+    cancelled.sort_unstable();
+    assert_eq!(vec![1, 3], cancelled);
+}
@@ -0,0 +1,163 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use bytes::BytesMut;
+use futures::stream;
+use rsocket_rust::error::RSocketError;
+use rsocket_rust::extension::{CompositeMetadata, DeadlineMetadata, MimeType};
+use rsocket_rust::prelude::*;
+use rsocket_rust::utils::Writeable;
+use rsocket_rust::{async_trait, Result};
+use rsocket_rust_transport_tcp::{TcpClientTransport, TcpServerTransport};
+
+fn init() {
+    let _ = env_logger::builder()
+        .format_timestamp_millis()
+        .is_test(true)
+        .try_init();
+}
+
+/// Responder answering after half a second, with the deadline it was given if any.
+#[derive(Clone, Default)]
+struct SlowRSocket {
+    finished: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl RSocket for SlowRSocket {
+    async fn metadata_push(&self, _req: Payload) -> Result<()> {
+        Ok(())
+    }
+
+    async fn fire_and_forget(&self, _req: Payload) -> Result<()> {
+        Ok(())
+    }
+
+    async fn request_response(&self, req: Payload) -> Result<Option<Payload>> {
+        let budget = req
+            .metadata()
+            .and_then(|it| CompositeMetadata::decode(&mut BytesMut::from(&it[..])).ok())
+            .and_then(|it| DeadlineMetadata::find(&it))
+            .map(|it| it.get_budget().as_millis().to_string())
+            .unwrap_or_default();
+        if req.data_utf8() == Some("slow") {
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+        self.finished.fetch_add(1, Ordering::SeqCst);
+        Ok(Some(Payload::builder().set_data_utf8(&budget).build()))
+    }
+
+    fn request_stream(&self, req: Payload) -> Flux<Result<Payload>> {
+        Box::pin(stream::once(async move { Ok(req) }).chain(stream::pending()))
+    }
+
+    fn request_channel(&self, reqs: Flux<Result<Payload>>) -> Flux<Result<Payload>> {
+        reqs
+    }
+}
+
+async fn serve(addr: &'static str, responder: SlowRSocket) {
+    tokio::spawn(async move {
+        RSocketFactory::receive()
+            .transport(TcpServerTransport::from(addr))
+            .acceptor(Box::new(move |_setup, _socket| {
+                Ok(Box::new(responder.clone()))
+            }))
+            .serve()
+            .await
+    });
+    tokio::time::sleep(Duration::from_millis(300)).await;
+}
+
+fn is_timeout(e: &anyhow::Error) -> bool {
+    matches!(
+        e.downcast_ref::<RSocketError>(),
+        Some(RSocketError::Timeout(_))
+    )
+}
+
+#[tokio::main]
+#[test]
+async fn test_request_timeout() {
+    init();
+    serve("127.0.0.1:8007", SlowRSocket::default()).await;
+    let cli = RSocketFactory::connect()
+        .transport(TcpClientTransport::from("127.0.0.1:8007"))
+        .request_timeout(Duration::from_millis(100))
+        .start()
+        .await
+        .unwrap();
+
+    let e = cli
+        .request_response(Payload::from("slow"))
+        .await
+        .unwrap_err();
+    assert!(is_timeout(&e), "unexpected error: {}", e);
+    assert!(cli.active_streams().is_empty());
+
+    // a deadline of its own overrides the default one.
+    let res = cli
+        .request_response_with(Payload::from("slow"), Duration::from_secs(2))
+        .await;
+    assert!(res.unwrap().is_some());
+
+    let mut results = cli.request_stream(Payload::from("hello"));
+    assert!(results.next().await.unwrap().is_ok());
+    let e = results.next().await.unwrap().unwrap_err();
+    assert!(is_timeout(&e), "unexpected error: {}", e);
+    assert!(results.next().await.is_none());
+    assert!(cli.active_streams().is_empty());
+}
+
+#[tokio::main]
+#[test]
+async fn test_propagate_deadline() {
+    init();
+    serve("127.0.0.1:8008", SlowRSocket::default()).await;
+    let cli = RSocketFactory::connect()
+        .transport(TcpClientTransport::from("127.0.0.1:8008"))
+        .metadata_mime_type(MimeType::MESSAGE_X_RSOCKET_COMPOSITE_METADATA_V0.to_string())
+        .request_timeout(Duration::from_secs(3))
+        .propagate_deadline()
+        .start()
+        .await
+        .unwrap();
+
+    let res = cli.request_response(Payload::from("fast")).await.unwrap();
+    assert_eq!(Some("3000"), res.unwrap().data_utf8());
+}
+
+#[tokio::main]
+#[test]
+async fn test_enforce_deadline() {
+    init();
+    let responder = SlowRSocket::default();
+    serve("127.0.0.1:8009", responder.clone()).await;
+    let cli = RSocketFactory::connect()
+        .transport(TcpClientTransport::from("127.0.0.1:8009"))
+        .metadata_mime_type(MimeType::MESSAGE_X_RSOCKET_COMPOSITE_METADATA_V0.to_string())
+        .start()
+        .await
+        .unwrap();
+
+    // the requester does not time out by itself, the responder enforces the deadline.
+    let metadata = CompositeMetadata::builder()
+        .push(
+            DeadlineMetadata::mime_type(),
+            DeadlineMetadata::new(Duration::from_millis(100)).bytes(),
+        )
+        .build();
+    let req = Payload::builder()
+        .set_data_utf8("slow")
+        .set_metadata(metadata.bytes())
+        .build();
+    let e = cli.request_response(req).await.unwrap_err();
+    match e.downcast_ref::<RSocketError>() {
+        Some(RSocketError::RequestCancelled(_)) => (),
+        _ => panic!("unexpected error: {}", e),
+    }
+
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert_eq!(0, responder.finished.load(Ordering::SeqCst));
+}
@@ -0,0 +1,109 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use rsocket_rust::prelude::*;
+use rsocket_rust::{async_trait, Client, Result};
+use rsocket_rust_transport_tcp::{TcpClientTransport, TcpServerTransport};
+
+fn init() {
+    let _ = env_logger::builder()
+        .format_timestamp_millis()
+        .is_test(true)
+        .try_init();
+}
+
+/// Responder recording fire_and_forget payloads, the first one taking a while.
+#[derive(Clone, Default)]
+struct SlowRecorder {
+    received: Arc<Mutex<Vec<String>>>,
+}
+
+#[async_trait]
+impl RSocket for SlowRecorder {
+    async fn metadata_push(&self, _req: Payload) -> Result<()> {
+        Ok(())
+    }
+
+    async fn fire_and_forget(&self, req: Payload) -> Result<()> {
+        let data = req.data_utf8().unwrap_or_default().to_string();
+        if data == "slow" {
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+        self.received.lock().unwrap().push(data);
+        Ok(())
+    }
+
+    async fn request_response(&self, req: Payload) -> Result<Option<Payload>> {
+        Ok(Some(req))
+    }
+
+    fn request_stream(&self, _req: Payload) -> Flux<Result<Payload>> {
+        Box::pin(futures::stream::empty())
+    }
+
+    fn request_channel(&self, reqs: Flux<Result<Payload>>) -> Flux<Result<Payload>> {
+        reqs
+    }
+}
+
+async fn connect(addr: &'static str, opts: DispatchOptions, responder: SlowRecorder) -> Client {
+    tokio::spawn(async move {
+        RSocketFactory::receive()
+            .transport(TcpServerTransport::from(addr))
+            .acceptor(Box::new(move |_setup, _socket| {
+                Ok(Box::new(responder.clone()))
+            }))
+            .dispatch_options(opts)
+            .serve()
+            .await
+    });
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    RSocketFactory::connect()
+        .transport(TcpClientTransport::from(addr))
+        .start()
+        .await
+        .unwrap()
+}
+
+#[tokio::main]
+#[test]
+async fn test_slow_fire_and_forget_does_not_block() {
+    init();
+    let responder = SlowRecorder::default();
+    let cli = connect("127.0.0.1:7994", DispatchOptions::new(), responder.clone()).await;
+
+    cli.fire_and_forget(Payload::from("slow")).await.unwrap();
+    cli.fire_and_forget(Payload::from("fast")).await.unwrap();
+    let res = tokio::time::timeout(
+        Duration::from_millis(200),
+        cli.request_response(Payload::from("hello")),
+    )
+    .await
+    .expect("request_response should not wait for the fire_and_forget handler");
+    assert!(res.unwrap().is_some());
+
+    tokio::time::sleep(Duration::from_millis(600)).await;
+    assert_eq!(vec!["fast", "slow"], *responder.received.lock().unwrap());
+}
+
+#[tokio::main]
+#[test]
+async fn test_ordered_fire_and_forget() {
+    init();
+    let responder = SlowRecorder::default();
+    let opts = DispatchOptions::new().ordered(true);
+    let cli = connect("127.0.0.1:7995", opts, responder.clone()).await;
+
+    cli.fire_and_forget(Payload::from("slow")).await.unwrap();
+    cli.fire_and_forget(Payload::from("fast")).await.unwrap();
+    let res = tokio::time::timeout(
+        Duration::from_millis(200),
+        cli.request_response(Payload::from("hello")),
+    )
+    .await
+    .expect("request_response should not wait for the fire_and_forget handler");
+    assert!(res.unwrap().is_some());
+
+    tokio::time::sleep(Duration::from_millis(600)).await;
+    assert_eq!(vec!["slow", "fast"], *responder.received.lock().unwrap());
+}
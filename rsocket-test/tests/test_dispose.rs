@@ -0,0 +1,235 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use rsocket_rust::error::{self, RSocketError};
+use rsocket_rust::frame::{self, Body, Frame};
+use rsocket_rust::prelude::*;
+use rsocket_rust::utils::Writeable;
+use rsocket_rust::{async_trait, stream, Client, Result};
+use rsocket_rust_transport_tcp::{TcpClientTransport, TcpServerTransport};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+fn init() {
+    let _ = env_logger::builder()
+        .format_timestamp_millis()
+        .is_test(true)
+        .try_init();
+}
+
+/// Responder answering slowly, with a stream which never ends.
+struct SlowRSocket;
+
+#[async_trait]
+impl RSocket for SlowRSocket {
+    async fn metadata_push(&self, _req: Payload) -> Result<()> {
+        Ok(())
+    }
+
+    async fn fire_and_forget(&self, _req: Payload) -> Result<()> {
+        Ok(())
+    }
+
+    async fn request_response(&self, req: Payload) -> Result<Option<Payload>> {
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        Ok(Some(req))
+    }
+
+    fn request_stream(&self, _req: Payload) -> Flux<Result<Payload>> {
+        Box::pin(stream! {
+            loop {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                yield Ok(Payload::from("tick"));
+            }
+        })
+    }
+
+    fn request_channel(&self, reqs: Flux<Result<Payload>>) -> Flux<Result<Payload>> {
+        reqs
+    }
+}
+
+async fn connect(addr: &'static str, closed: Arc<AtomicBool>) -> Client {
+    tokio::spawn(async move {
+        RSocketFactory::receive()
+            .transport(TcpServerTransport::from(addr))
+            .acceptor(Box::new(|_setup, _socket| Ok(Box::new(SlowRSocket))))
+            .serve()
+            .await
+    });
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    RSocketFactory::connect()
+        .transport(TcpClientTransport::from(addr))
+        .on_close(Box::new(move || closed.store(true, Ordering::SeqCst)))
+        .start()
+        .await
+        .unwrap()
+}
+
+async fn write(socket: &mut TcpStream, frame: Frame) {
+    let mut raw = BytesMut::new();
+    frame.write_to(&mut raw);
+    let mut bf = BytesMut::new();
+    bf.put_uint(raw.len() as u64, 3);
+    bf.put_slice(&raw);
+    socket.write_all(&bf).await.unwrap();
+}
+
+async fn read(socket: &mut TcpStream) -> Frame {
+    let mut len = [0u8; 3];
+    socket.read_exact(&mut len).await.unwrap();
+    let mut bf = BytesMut::new();
+    bf.resize((&len[..]).get_uint(3) as usize, 0);
+    socket.read_exact(&mut bf).await.unwrap();
+    Frame::decode(&mut bf).unwrap()
+}
+
+fn is_closed(e: &anyhow::Error) -> bool {
+    matches!(
+        e.downcast_ref::<RSocketError>(),
+        Some(RSocketError::ConnectionClosed(_))
+    )
+}
+
+#[tokio::main]
+#[test]
+async fn test_dispose_gracefully_drains_requests() {
+    init();
+    let closed = Arc::new(AtomicBool::new(false));
+    let cli = connect("127.0.0.1:7990", closed.clone()).await;
+
+    let requester = cli.clone();
+    let pending =
+        tokio::spawn(async move { requester.request_response(Payload::from("hello")).await });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    tokio::time::timeout(
+        Duration::from_secs(2),
+        cli.dispose_gracefully(Duration::from_secs(1)),
+    )
+    .await
+    .expect("dispose should complete once drained");
+    let res = pending.await.unwrap().unwrap();
+    assert_eq!("hello", res.unwrap().data_utf8().unwrap());
+    assert!(closed.load(Ordering::SeqCst));
+
+    let res = cli.request_response(Payload::from("hello")).await;
+    assert!(is_closed(&res.unwrap_err()));
+}
+
+#[tokio::main]
+#[test]
+async fn test_dispose_gracefully_cancels_after_timeout() {
+    init();
+    let closed = Arc::new(AtomicBool::new(false));
+    let cli = connect("127.0.0.1:7991", closed.clone()).await;
+
+    let mut results = cli.request_stream(Payload::from("hello"));
+    assert!(results.next().await.unwrap().is_ok());
+
+    tokio::time::timeout(
+        Duration::from_secs(2),
+        cli.dispose_gracefully(Duration::from_millis(100)),
+    )
+    .await
+    .expect("dispose should give up after the timeout");
+    assert!(closed.load(Ordering::SeqCst));
+
+    // the stream ends once the connection is closed.
+    let rest = tokio::time::timeout(Duration::from_secs(1), async {
+        while let Some(next) = results.next().await {
+            if next.is_err() {
+                break;
+            }
+        }
+    })
+    .await;
+    assert!(rest.is_ok());
+    let res = cli.fire_and_forget(Payload::from("hello")).await;
+    assert!(is_closed(&res.unwrap_err()));
+}
+
+#[tokio::main]
+#[test]
+async fn test_server_shutdown_drains_connections() {
+    init();
+    let handle = ShutdownHandle::new();
+    let server = tokio::spawn(
+        RSocketFactory::receive()
+            .transport(TcpServerTransport::from("127.0.0.1:7992"))
+            .acceptor(Box::new(|_setup, _socket| Ok(Box::new(SlowRSocket))))
+            .shutdown_handle(handle.clone())
+            .serve(),
+    );
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    let closed = Arc::new(AtomicBool::new(false));
+    let flag = closed.clone();
+    let cli = RSocketFactory::connect()
+        .transport(TcpClientTransport::from("127.0.0.1:7992"))
+        .on_close(Box::new(move || flag.store(true, Ordering::SeqCst)))
+        .start()
+        .await
+        .unwrap();
+    let requester = cli.clone();
+    let pending =
+        tokio::spawn(async move { requester.request_response(Payload::from("hello")).await });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    handle.shutdown(Duration::from_secs(1));
+    tokio::time::timeout(Duration::from_secs(2), server)
+        .await
+        .expect("serve should return once drained")
+        .unwrap()
+        .unwrap();
+    let res = pending.await.unwrap().unwrap();
+    assert_eq!("hello", res.unwrap().data_utf8().unwrap());
+
+    tokio::time::timeout(Duration::from_secs(1), cli.clone().wait_for_close())
+        .await
+        .expect("client should be closed by the server");
+    assert!(closed.load(Ordering::SeqCst));
+    let res = cli.request_response(Payload::from("hello")).await;
+    assert!(is_closed(&res.unwrap_err()));
+}
+
+#[tokio::main]
+#[test]
+async fn test_connection_close_follows_drain() {
+    init();
+    let handle = ShutdownHandle::new();
+    tokio::spawn(
+        RSocketFactory::receive()
+            .transport(TcpServerTransport::from("127.0.0.1:8038"))
+            .acceptor(Box::new(|_setup, _socket| Ok(Box::new(SlowRSocket))))
+            .shutdown_handle(handle.clone())
+            .serve(),
+    );
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    let mut socket = TcpStream::connect("127.0.0.1:8038").await.unwrap();
+    let setup = frame::Setup::builder(0, 0)
+        .set_mime_data("text/plain")
+        .set_mime_metadata("text/plain")
+        .build();
+    write(&mut socket, setup).await;
+    let request = frame::RequestResponse::builder(1, 0)
+        .set_data(Bytes::from("hello"))
+        .build();
+    write(&mut socket, request).await;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    handle.shutdown(Duration::from_secs(1));
+
+    // the peer is told the connection closes only once the response has been sent.
+    let response = read(&mut socket).await;
+    assert_eq!(1, response.get_stream_id());
+    assert!(matches!(response.get_body_ref(), Body::Payload(_)));
+    let closing = read(&mut socket).await;
+    match closing.get_body_ref() {
+        Body::Error(e) => assert_eq!(error::ERR_CONN_CLOSED, e.get_code()),
+        other => panic!("expect CONNECTION_CLOSE, got {:?}", other),
+    }
+}
@@ -0,0 +1,124 @@
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures::stream;
+use rsocket_rust::error::{self, RSocketError};
+use rsocket_rust::prelude::*;
+use rsocket_rust::{async_trait, Client, Result};
+use rsocket_rust_transport_tcp::{TcpClientTransport, TcpServerTransport};
+
+fn init() {
+    let _ = env_logger::builder()
+        .format_timestamp_millis()
+        .is_test(true)
+        .try_init();
+}
+
+const RETRY_LATER: u32 = 0x0000_0301;
+
+/// Responder failing in the way its request asks for.
+struct FailingRSocket;
+
+fn fail(req: &Payload) -> anyhow::Error {
+    match req.data_utf8().unwrap_or_default() {
+        "reject" => RSocketError::RequestRejected("too busy".into()).into(),
+        "invalid" => RSocketError::RequestInvalid("bad request".into()).into(),
+        "custom" => RSocketError::custom(RETRY_LATER, Bytes::from_static(&[0xde, 0xad])).into(),
+        "reserved" => RSocketError::custom(0x0000_0205, "not an application code").into(),
+        _ => anyhow::anyhow!("oops"),
+    }
+}
+
+#[async_trait]
+impl RSocket for FailingRSocket {
+    async fn metadata_push(&self, _req: Payload) -> Result<()> {
+        Ok(())
+    }
+
+    async fn fire_and_forget(&self, _req: Payload) -> Result<()> {
+        Ok(())
+    }
+
+    async fn request_response(&self, req: Payload) -> Result<Option<Payload>> {
+        Err(fail(&req))
+    }
+
+    fn request_stream(&self, req: Payload) -> Flux<Result<Payload>> {
+        let e = fail(&req);
+        Box::pin(stream::iter(vec![Ok(req), Err(e)]))
+    }
+
+    fn request_channel(&self, _reqs: Flux<Result<Payload>>) -> Flux<Result<Payload>> {
+        Box::pin(stream::empty())
+    }
+}
+
+async fn connect(addr: &'static str) -> Client {
+    tokio::spawn(async move {
+        RSocketFactory::receive()
+            .transport(TcpServerTransport::from(addr))
+            .acceptor(Box::new(|_setup, _socket| Ok(Box::new(FailingRSocket))))
+            .serve()
+            .await
+    });
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    RSocketFactory::connect()
+        .transport(TcpClientTransport::from(addr))
+        .start()
+        .await
+        .unwrap()
+}
+
+async fn request(cli: &Client, data: &'static str) -> (u32, Bytes) {
+    let e = cli.request_response(Payload::from(data)).await.unwrap_err();
+    let e = e
+        .downcast_ref::<RSocketError>()
+        .expect("not an RSocketError");
+    (e.code().unwrap(), e.data().unwrap())
+}
+
+#[tokio::main]
+#[test]
+async fn test_error_codes() {
+    init();
+    let cli = connect("127.0.0.1:8012").await;
+
+    let (code, data) = request(&cli, "reject").await;
+    assert_eq!(error::ERR_REJECTED, code);
+    assert_eq!("too busy", data);
+
+    let (code, data) = request(&cli, "invalid").await;
+    assert_eq!(error::ERR_INVALID, code);
+    assert_eq!("bad request", data);
+
+    let (code, data) = request(&cli, "custom").await;
+    assert_eq!(RETRY_LATER, code);
+    assert_eq!(&[0xde, 0xad][..], &data[..]);
+
+    // codes out of the application range are not for responders to send.
+    let (code, _) = request(&cli, "reserved").await;
+    assert_eq!(error::ERR_APPLICATION, code);
+
+    let (code, data) = request(&cli, "bug").await;
+    assert_eq!(error::ERR_APPLICATION, code);
+    assert_eq!("oops", data);
+}
+
+#[tokio::main]
+#[test]
+async fn test_stream_error_code() {
+    init();
+    let cli = connect("127.0.0.1:8013").await;
+
+    let mut results = cli.request_stream(Payload::from("custom"));
+    assert!(results.next().await.unwrap().is_ok());
+    let e = results.next().await.unwrap().unwrap_err();
+    match e.downcast_ref::<RSocketError>() {
+        Some(RSocketError::Custom { code, data }) => {
+            assert_eq!(RETRY_LATER, *code);
+            assert_eq!(&[0xde, 0xad][..], &data[..]);
+        }
+        _ => panic!("unexpected error: {}", e),
+    }
+    assert!(results.next().await.is_none());
+}
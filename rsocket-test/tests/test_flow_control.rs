@@ -0,0 +1,177 @@
+#[macro_use]
+extern crate log;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::future;
+use rsocket_rust::prelude::*;
+use rsocket_rust::utils::EchoRSocket;
+use rsocket_rust::{async_trait, stream, Client, Result};
+use rsocket_rust_transport_tcp::{TcpClientTransport, TcpServerTransport};
+
+fn init() {
+    let _ = env_logger::builder()
+        .format_timestamp_millis()
+        .is_test(true)
+        .try_init();
+}
+
+/// Responder which counts how many elements have been pulled from its streams.
+struct CountingRSocket {
+    produced: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl RSocket for CountingRSocket {
+    async fn metadata_push(&self, _req: Payload) -> Result<()> {
+        Ok(())
+    }
+
+    async fn fire_and_forget(&self, _req: Payload) -> Result<()> {
+        Ok(())
+    }
+
+    async fn request_response(&self, req: Payload) -> Result<Option<Payload>> {
+        Ok(Some(req))
+    }
+
+    fn request_stream(&self, _req: Payload) -> Flux<Result<Payload>> {
+        let produced = self.produced.clone();
+        Box::pin(stream! {
+            loop {
+                produced.fetch_add(1, Ordering::SeqCst);
+                yield Ok(Payload::from("next"));
+            }
+        })
+    }
+
+    fn request_channel(&self, mut reqs: Flux<Result<Payload>>) -> Flux<Result<Payload>> {
+        // consume a single element of the inbound stream, then stall.
+        Box::pin(stream! {
+            if let Some(it) = reqs.next().await {
+                yield it;
+            }
+            future::pending::<()>().await;
+        })
+    }
+}
+
+async fn serve(addr: &'static str, produced: Arc<AtomicUsize>) -> Client {
+    tokio::spawn(async move {
+        RSocketFactory::receive()
+            .transport(TcpServerTransport::from(addr))
+            .acceptor(Box::new(move |setup, _socket| {
+                info!("accept setup: {:?}", setup);
+                Ok(Box::new(CountingRSocket {
+                    produced: produced.clone(),
+                }))
+            }))
+            .serve()
+            .await
+    });
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    RSocketFactory::connect()
+        .acceptor(Box::new(|| Box::new(EchoRSocket)))
+        .transport(TcpClientTransport::from(addr))
+        .start()
+        .await
+        .unwrap()
+}
+
+#[tokio::main]
+#[test]
+async fn test_request_stream_respects_request_n() {
+    init();
+    let produced = Arc::new(AtomicUsize::new(0));
+    let cli = serve("127.0.0.1:7979", produced.clone()).await;
+
+    let mut results = cli.request_stream(Payload::from("start"));
+    assert!(results.next().await.unwrap().is_ok());
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    // only the initial demand has been produced.
+    assert_eq!(32, produced.load(Ordering::SeqCst));
+
+    // consuming three quarters of the demand triggers a REQUEST_N.
+    for _ in 0..24 {
+        assert!(results.next().await.unwrap().is_ok());
+    }
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert_eq!(56, produced.load(Ordering::SeqCst));
+}
+
+#[tokio::main]
+#[test]
+async fn test_request_channel_respects_request_n() {
+    init();
+    let cli = serve("127.0.0.1:7980", Arc::new(AtomicUsize::new(0))).await;
+
+    let sent = Arc::new(AtomicUsize::new(0));
+    let counter = sent.clone();
+    let mut results = cli.request_channel(Box::pin(stream! {
+        loop {
+            counter.fetch_add(1, Ordering::SeqCst);
+            yield Ok(Payload::from("next"));
+        }
+    }));
+    assert!(results.next().await.unwrap().is_ok());
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    // the first payload plus the demand granted by the responder.
+    assert_eq!(32, sent.load(Ordering::SeqCst));
+}
+
+#[tokio::main]
+#[test]
+async fn test_request_stream_with_explicit_demand() {
+    init();
+    let produced = Arc::new(AtomicUsize::new(0));
+    let cli = serve("127.0.0.1:7981", produced.clone()).await;
+
+    let opts = StreamOptions::new().initial_request_n(5).limit_rate(0);
+    let mut sub = cli.request_stream_with(Payload::from("start"), opts);
+    for _ in 0..5 {
+        assert!(sub.next().await.unwrap().is_ok());
+    }
+    let next = tokio::time::timeout(Duration::from_millis(300), sub.next()).await;
+    assert!(next.is_err(), "no more demand, no more elements");
+    assert_eq!(5, produced.load(Ordering::SeqCst));
+
+    sub.request(3).unwrap();
+    for _ in 0..3 {
+        assert!(sub.next().await.unwrap().is_ok());
+    }
+    assert_eq!(8, produced.load(Ordering::SeqCst));
+
+    sub.cancel().unwrap();
+    assert!(sub.next().await.is_none());
+}
+
+#[tokio::main]
+#[test]
+async fn test_unconsumed_demand_does_not_hold_back_connection() {
+    init();
+    let produced = Arc::new(AtomicUsize::new(0));
+    let cli = serve("127.0.0.1:8026", produced.clone()).await;
+
+    // demand far above what the first request asked for, none of it consumed yet.
+    let opts = StreamOptions::new().initial_request_n(1).limit_rate(0);
+    let mut sub = cli.request_stream_with(Payload::from("start"), opts);
+    assert!(sub.next().await.unwrap().is_ok());
+    sub.request(1000).unwrap();
+    while produced.load(Ordering::SeqCst) < 1001 {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    let res = tokio::time::timeout(
+        Duration::from_secs(3),
+        cli.request_response(Payload::from("ping")),
+    )
+    .await
+    .expect("the connection is held back by the unconsumed stream");
+    assert_eq!(Some("ping"), res.unwrap().unwrap().data_utf8());
+    for _ in 0..1000 {
+        assert!(sub.next().await.unwrap().is_ok());
+    }
+}
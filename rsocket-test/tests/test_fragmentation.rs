@@ -0,0 +1,165 @@
+use std::time::Duration;
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use rsocket_rust::error::{ERR_CANCELED, ERR_CONN_FAILED};
+use rsocket_rust::frame::{self, Body, Frame};
+use rsocket_rust::prelude::*;
+use rsocket_rust::utils::{EchoRSocket, Writeable};
+use rsocket_rust_transport_tcp::TcpServerTransport;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+fn init() {
+    let _ = env_logger::builder()
+        .format_timestamp_millis()
+        .is_test(true)
+        .try_init();
+}
+
+async fn serve(addr: &'static str, mtu: usize, opts: ReassemblyOptions) {
+    tokio::spawn(async move {
+        RSocketFactory::receive()
+            .transport(TcpServerTransport::from(addr))
+            .fragment(mtu)
+            .reassembly_options(opts)
+            .acceptor(Box::new(|_setup, _socket| Ok(Box::new(EchoRSocket))))
+            .serve()
+            .await
+    });
+    tokio::time::sleep(Duration::from_millis(300)).await;
+}
+
+async fn connect(addr: &'static str) -> TcpStream {
+    let mut socket = TcpStream::connect(addr).await.unwrap();
+    let setup = frame::Setup::builder(0, 0)
+        .set_mime_data("text/plain")
+        .set_mime_metadata("text/plain")
+        .build();
+    write(&mut socket, setup).await;
+    socket
+}
+
+async fn write(socket: &mut TcpStream, frame: Frame) {
+    let mut bf = BytesMut::new();
+    frame.write_to(&mut bf);
+    let mut raw = BytesMut::new();
+    raw.put_uint(bf.len() as u64, 3);
+    raw.put_slice(&bf);
+    socket.write_all(&raw).await.unwrap();
+}
+
+/// Reads a frame, along with the length it had on the wire.
+async fn read(socket: &mut TcpStream) -> Option<(usize, Frame)> {
+    let mut len = [0u8; 3];
+    let read = tokio::time::timeout(Duration::from_secs(2), socket.read_exact(&mut len))
+        .await
+        .expect("expect a frame");
+    if read.is_err() {
+        return None;
+    }
+    let len = (&len[..]).get_uint(3) as usize;
+    let mut bf = BytesMut::new();
+    bf.resize(len, 0);
+    socket.read_exact(&mut bf).await.unwrap();
+    Some((len, Frame::decode(&mut bf).unwrap()))
+}
+
+async fn read_error(socket: &mut TcpStream) -> (u32, u32) {
+    let (_, frame) = read(socket).await.expect("expect an ERROR frame");
+    let sid = frame.get_stream_id();
+    match frame.get_body() {
+        Body::Error(e) => (sid, e.get_code()),
+        other => panic!("expect an ERROR frame, got {:?}", other),
+    }
+}
+
+/// A REQUEST_RESPONSE fragment followed by more, PAYLOAD frames after the first one.
+fn fragment(sid: u32, first: bool, size: usize) -> Frame {
+    let data = Bytes::from(vec![b'x'; size]);
+    if first {
+        frame::RequestResponse::builder(sid, Frame::FLAG_FOLLOW)
+            .set_data(data)
+            .build()
+    } else {
+        frame::Payload::builder(sid, Frame::FLAG_FOLLOW)
+            .set_data(data)
+            .build()
+    }
+}
+
+#[tokio::main]
+#[test]
+async fn test_fragment_channel_responses() {
+    init();
+    serve("127.0.0.1:8020", 64, ReassemblyOptions::new()).await;
+    let mut socket = connect("127.0.0.1:8020").await;
+    let data: Vec<u8> = (0..500).map(|it| (it % 251) as u8).collect();
+    // fragments carrying metadata spend 3 bytes of the mtu on its length.
+    let metadata = Bytes::from(vec![b'm'; 100]);
+    let request = frame::RequestChannel::builder(1, Frame::FLAG_COMPLETE)
+        .set_initial_request_n(8)
+        .set_data(Bytes::from(data.clone()))
+        .set_metadata(metadata.clone())
+        .build();
+    write(&mut socket, request).await;
+
+    let (mut joined_data, mut joined_metadata) = (BytesMut::new(), BytesMut::new());
+    loop {
+        let (len, frame) = read(&mut socket).await.unwrap();
+        assert!(len <= 64, "frame of {} bytes exceeds the mtu", len);
+        let follow = frame.get_flag() & Frame::FLAG_FOLLOW != 0;
+        match frame.get_body() {
+            Body::Payload(it) => {
+                let (d, m) = it.split();
+                d.into_iter().for_each(|it| joined_data.put(it));
+                m.into_iter().for_each(|it| joined_metadata.put(it));
+            }
+            other => panic!("expect a PAYLOAD frame, got {:?}", other),
+        }
+        if !follow {
+            break;
+        }
+    }
+    assert_eq!(&data[..], &joined_data[..]);
+    assert_eq!(&metadata[..], &joined_metadata[..]);
+}
+
+#[tokio::main]
+#[test]
+async fn test_reassembly_max_payload_size() {
+    init();
+    let opts = ReassemblyOptions::new().max_payload_size(100);
+    serve("127.0.0.1:8021", 0, opts).await;
+    let mut socket = connect("127.0.0.1:8021").await;
+    write(&mut socket, fragment(1, true, 60)).await;
+    write(&mut socket, fragment(1, false, 60)).await;
+    assert_eq!((1, ERR_CANCELED), read_error(&mut socket).await);
+
+    // the rest of the fragments are ignored, and the connection stays usable.
+    write(&mut socket, fragment(1, false, 60)).await;
+    let request = frame::RequestResponse::builder(3, 0)
+        .set_data(Bytes::from("hello"))
+        .build();
+    write(&mut socket, request).await;
+    let (_, response) = read(&mut socket).await.unwrap();
+    assert_eq!(3, response.get_stream_id());
+    assert!(matches!(response.get_body_ref(), Body::Payload(_)));
+}
+
+#[tokio::main]
+#[test]
+async fn test_reassembly_max_partial_streams() {
+    init();
+    let opts = ReassemblyOptions::new().max_partial_streams(2);
+    serve("127.0.0.1:8022", 0, opts).await;
+    let mut socket = connect("127.0.0.1:8022").await;
+    write(&mut socket, fragment(1, true, 10)).await;
+    write(&mut socket, fragment(3, true, 10)).await;
+    write(&mut socket, fragment(3, false, 10)).await;
+    write(&mut socket, fragment(5, true, 10)).await;
+    assert_eq!((0, ERR_CONN_FAILED), read_error(&mut socket).await);
+    assert!(
+        read(&mut socket).await.is_none(),
+        "connection should be closed"
+    );
+}
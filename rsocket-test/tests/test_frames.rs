@@ -3,8 +3,10 @@ extern crate hex;
 extern crate rsocket_rust;
 
 use bytes::{Bytes, BytesMut};
+use futures::SinkExt;
 use rsocket_rust::frame::*;
-use rsocket_rust::utils::Writeable;
+use rsocket_rust::prelude::Payload as Message;
+use rsocket_rust::utils::{u24, LengthBasedFrameWriter, Writeable};
 
 #[test]
 fn test_setup() {
@@ -78,6 +80,84 @@ fn test_metadata_push() {
     try_codec(f);
 }
 
+#[test]
+fn test_metadata_push_on_stream_zero() {
+    let f = MetadataPush::builder(0, 0)
+        .set_metadata(Bytes::from("Hello Rust!"))
+        .build();
+    assert_eq!(Frame::FLAG_METADATA, f.get_flag());
+    let mut bf = BytesMut::new();
+    f.write_to(&mut bf);
+    assert_eq!("000000003100", hex::encode(&bf[..6]));
+    try_codec(f);
+
+    // empty metadata is still metadata.
+    let f = MetadataPush::builder(0, 0).build();
+    assert_eq!(Frame::FLAG_METADATA, f.get_flag());
+    try_codec(f);
+}
+
+#[test]
+fn test_payload_metadata_only() {
+    let f = Payload::builder(1234, Frame::FLAG_NEXT)
+        .set_metadata(Bytes::from("foobar"))
+        .build();
+    try_codec(f);
+
+    let f = Payload::builder(1234, Frame::FLAG_NEXT)
+        .set_metadata(Bytes::new())
+        .build();
+    try_codec(f);
+}
+
+#[test]
+fn test_payload_empty_data() {
+    let f = Payload::builder(1234, Frame::FLAG_NEXT)
+        .set_data(Bytes::new())
+        .build();
+    match f.get_body_ref() {
+        Body::Payload(it) => assert_eq!(Some(&Bytes::new()), it.get_data()),
+        it => panic!("unexpected body: {:?}", it),
+    }
+    // data has no flag, empty data takes no room on the wire.
+    let none = Payload::builder(1234, Frame::FLAG_NEXT).build();
+    assert_eq!(none.bytes(), f.bytes());
+    let f = Payload::builder(1234, Frame::FLAG_NEXT)
+        .set_all((Some(Bytes::new()), None))
+        .build();
+    assert_eq!(none.bytes(), f.bytes());
+}
+
+#[test]
+fn test_payload_round_trip() {
+    let cases = vec![
+        Message::new(None, None),
+        Message::new(Some(Bytes::new()), None),
+        Message::new(None, Some(Bytes::new())),
+        Message::new(None, Some(Bytes::from("foobar"))),
+        Message::new(Some(Bytes::from("hello")), Some(Bytes::new())),
+        Message::from(("hello", "foobar")),
+    ];
+    for input in cases {
+        let (data, metadata) = (input.data().cloned(), input.metadata().cloned());
+        let f = Payload::builder(1234, Frame::FLAG_NEXT)
+            .set_all(input.split())
+            .build();
+        let mut bf = BytesMut::new();
+        f.write_to(&mut bf);
+        let output = match Frame::decode(&mut bf).unwrap().get_body() {
+            Body::Payload(it) => Message::from(it),
+            it => panic!("unexpected body: {:?}", it),
+        };
+        assert_eq!(data, output.data().cloned());
+        assert_eq!(metadata, output.metadata().cloned());
+    }
+    // empty data is no data, locally as well.
+    assert_eq!(None, Message::new(Some(Bytes::new()), None).data());
+    assert_eq!(None, Message::builder().set_data_utf8("").build().data());
+    assert_eq!(None, Message::from("").data_utf8());
+}
+
 #[test]
 fn test_request_n() {
     let f = RequestN::builder(1234, 0).set_n(77778888).build();
@@ -119,8 +199,52 @@ fn test_resume() {
     try_codec(f);
 }
 
+#[test]
+fn test_unknown() {
+    let f = Frame::new(
+        0,
+        Body::Unknown(0x30, Bytes::from("extension")),
+        Frame::FLAG_IGNORE,
+    );
+    try_codec(f);
+}
+
+#[tokio::main]
+#[test]
+async fn test_length_based_frame_writer() {
+    let frames = [
+        RequestN::builder(1, 0).set_n(16).build(),
+        Payload::builder(1, Frame::FLAG_NEXT)
+            .set_data(Bytes::from(vec![b'd'; 4096]))
+            .set_metadata(Bytes::from("small"))
+            .build(),
+        Cancel::builder(3, 0).build(),
+    ];
+    let mut written = Vec::new();
+    let mut writer = LengthBasedFrameWriter::new(&mut written);
+    for it in frames.iter() {
+        writer.feed(it.clone()).await.unwrap();
+    }
+    writer.flush().await.unwrap();
+    drop(writer);
+
+    // frames come out prefixed with their length, as the codec of the transports reads them.
+    let mut expected = BytesMut::new();
+    for it in frames.iter() {
+        u24::from(it.len()).write_to(&mut expected);
+        it.write_to(&mut expected);
+    }
+    assert_eq!(expected.to_vec(), written);
+}
+
 fn try_codec(f: Frame) {
     println!("******* codec: {:?}", f);
+    // the head followed by the payload it leaves out makes the whole frame.
+    let mut head = BytesMut::new();
+    for it in f.write_head_to(&mut head).iter().flatten() {
+        head.extend_from_slice(it);
+    }
+    assert_eq!(f.bytes(), head.to_vec());
     let mut bf = BytesMut::with_capacity(f.len() as usize);
     f.write_to(&mut bf);
     println!("####### encode: {}", hex::encode(bf.to_vec()));
@@ -0,0 +1,127 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use bytes::{Buf, BufMut, BytesMut};
+use rsocket_rust::error::ERR_CONN_FAILED;
+use rsocket_rust::frame::{self, Body, Frame};
+use rsocket_rust::prelude::*;
+use rsocket_rust::utils::{EchoRSocket, Writeable};
+use rsocket_rust_transport_tcp::{TcpClientTransport, TcpServerTransport};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+fn init() {
+    let _ = env_logger::builder()
+        .format_timestamp_millis()
+        .is_test(true)
+        .try_init();
+}
+
+#[tokio::main]
+#[test]
+async fn test_client_closes_silent_connection() {
+    init();
+    // a peer which accepts connections but never answers.
+    let listener = TcpListener::bind("127.0.0.1:7987").await.unwrap();
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut buf = vec![0u8; 4096];
+        while let Ok(n) = socket.read(&mut buf).await {
+            if n == 0 {
+                break;
+            }
+        }
+    });
+
+    let closed = Arc::new(AtomicBool::new(false));
+    let flag = closed.clone();
+    let cli = RSocketFactory::connect()
+        .transport(TcpClientTransport::from("127.0.0.1:7987"))
+        .keepalive(Duration::from_millis(100), Duration::from_millis(100), 3)
+        .on_close(Box::new(move || flag.store(true, Ordering::SeqCst)))
+        .start()
+        .await
+        .unwrap();
+
+    let res = tokio::time::timeout(
+        Duration::from_secs(2),
+        cli.request_response(Payload::from("hello")),
+    )
+    .await
+    .expect("pending request should fail once the connection is dead");
+    assert!(res.is_err());
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(closed.load(Ordering::SeqCst));
+}
+
+#[tokio::main]
+#[test]
+async fn test_server_closes_silent_connection() {
+    init();
+    tokio::spawn(async move {
+        RSocketFactory::receive()
+            .transport(TcpServerTransport::from("127.0.0.1:7988"))
+            .acceptor(Box::new(|_setup, _socket| Ok(Box::new(EchoRSocket))))
+            .serve()
+            .await
+    });
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    // send a SETUP frame, then stay silent.
+    let mut socket = TcpStream::connect("127.0.0.1:7988").await.unwrap();
+    let setup = frame::Setup::builder(0, 0)
+        .set_keepalive(Duration::from_millis(100))
+        .set_lifetime(Duration::from_millis(300))
+        .set_mime_data("text/plain")
+        .set_mime_metadata("text/plain")
+        .build();
+    let mut bf = BytesMut::new();
+    bf.put_uint(setup.len() as u64, 3);
+    setup.write_to(&mut bf);
+    socket.write_all(&bf).await.unwrap();
+
+    let mut received = vec![];
+    tokio::time::timeout(Duration::from_secs(2), socket.read_to_end(&mut received))
+        .await
+        .expect("server should close the connection")
+        .unwrap();
+    let mut bf = BytesMut::from(&received[..]);
+    let len = bf.get_uint(3) as usize;
+    let frame = Frame::decode(&mut bf.split_to(len)).unwrap();
+    match frame.get_body() {
+        Body::Error(e) => assert_eq!(ERR_CONN_FAILED, e.get_code()),
+        _ => panic!("expect an ERROR frame"),
+    }
+}
+
+#[tokio::main]
+#[test]
+async fn test_rtt() {
+    init();
+    tokio::spawn(async move {
+        RSocketFactory::receive()
+            .transport(TcpServerTransport::from("127.0.0.1:7989"))
+            .acceptor(Box::new(|_setup, _socket| Ok(Box::new(EchoRSocket))))
+            .serve()
+            .await
+    });
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    let cli = RSocketFactory::connect()
+        .transport(TcpClientTransport::from("127.0.0.1:7989"))
+        .keepalive(Duration::from_millis(100), Duration::from_secs(1), 3)
+        .start()
+        .await
+        .unwrap();
+    assert!(cli.rtt().is_none());
+
+    let mut samples = cli.rtt_samples();
+    let sample = tokio::time::timeout(Duration::from_secs(1), samples.next())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(sample < Duration::from_secs(1));
+    assert!(cli.rtt().is_some());
+    assert!(cli.rtt_ewma().is_some());
+}
@@ -0,0 +1,202 @@
+#[macro_use]
+extern crate log;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bytes::{Buf, BytesMut};
+use rsocket_rust::error::RSocketError;
+use rsocket_rust::frame::{Body, Frame};
+use rsocket_rust::prelude::*;
+use rsocket_rust::utils::EchoRSocket;
+use rsocket_rust::{async_trait, Client, Result};
+use rsocket_rust_transport_tcp::{TcpClientTransport, TcpServerTransport};
+use tokio::io::AsyncReadExt;
+use tokio::net::{TcpListener, TcpStream};
+
+fn init() {
+    let _ = env_logger::builder()
+        .format_timestamp_millis()
+        .is_test(true)
+        .try_init();
+}
+
+/// Responder whose streams and channels never complete.
+struct IdleRSocket;
+
+#[async_trait]
+impl RSocket for IdleRSocket {
+    async fn metadata_push(&self, _req: Payload) -> Result<()> {
+        Ok(())
+    }
+
+    async fn fire_and_forget(&self, _req: Payload) -> Result<()> {
+        Ok(())
+    }
+
+    async fn request_response(&self, req: Payload) -> Result<Option<Payload>> {
+        Ok(Some(req))
+    }
+
+    fn request_stream(&self, _req: Payload) -> Flux<Result<Payload>> {
+        Box::pin(futures::stream::pending())
+    }
+
+    fn request_channel(&self, _reqs: Flux<Result<Payload>>) -> Flux<Result<Payload>> {
+        Box::pin(futures::stream::pending())
+    }
+}
+
+async fn serve(addr: &'static str, strategy: impl LeaseStrategy + 'static) {
+    tokio::spawn(async move {
+        RSocketFactory::receive()
+            .transport(TcpServerTransport::from(addr))
+            .acceptor(Box::new(|setup, _socket| {
+                info!("accept setup: {:?}", setup);
+                Ok(Box::new(EchoRSocket))
+            }))
+            .lease(strategy)
+            .serve()
+            .await
+    });
+    tokio::time::sleep(Duration::from_millis(300)).await;
+}
+
+async fn connect(addr: &'static str) -> Client {
+    let cli = RSocketFactory::connect()
+        .transport(TcpClientTransport::from(addr))
+        .lease()
+        .start()
+        .await
+        .unwrap();
+    // wait for the first lease.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    cli
+}
+
+async fn read(socket: &mut TcpStream) -> Frame {
+    let mut len = [0u8; 3];
+    socket.read_exact(&mut len).await.unwrap();
+    let mut bf = BytesMut::new();
+    bf.resize((&len[..]).get_uint(3) as usize, 0);
+    socket.read_exact(&mut bf).await.unwrap();
+    Frame::decode(&mut bf).unwrap()
+}
+
+fn is_rejected(e: &anyhow::Error) -> bool {
+    matches!(
+        e.downcast_ref::<RSocketError>(),
+        Some(RSocketError::RequestRejected(_))
+    )
+}
+
+#[tokio::main]
+#[test]
+async fn test_lease_limits_requests() {
+    init();
+    serve("127.0.0.1:7985", |_active: usize| {
+        Some(Lease::new(Duration::from_millis(1000), 3))
+    })
+    .await;
+    let cli = connect("127.0.0.1:7985").await;
+
+    for _ in 0..3 {
+        let res = cli.request_response(Payload::from("hello")).await;
+        assert!(res.unwrap().is_some());
+    }
+    let res = cli.request_response(Payload::from("hello")).await;
+    assert!(is_rejected(&res.unwrap_err()));
+    let mut results = cli.request_stream(Payload::from("hello"));
+    assert!(is_rejected(&results.next().await.unwrap().unwrap_err()));
+
+    // the lease gets renewed once expired.
+    tokio::time::sleep(Duration::from_millis(1000)).await;
+    let res = cli.request_response(Payload::from("hello")).await;
+    assert!(res.unwrap().is_some());
+}
+
+#[tokio::main]
+#[test]
+async fn test_lease_not_granted() {
+    init();
+    serve("127.0.0.1:7986", |_active: usize| None).await;
+    let cli = connect("127.0.0.1:7986").await;
+
+    let res = cli.fire_and_forget(Payload::from("hello")).await;
+    assert!(is_rejected(&res.unwrap_err()));
+    // metadata push is not subject to leases.
+    assert!(cli
+        .metadata_push(Payload::from(("", "hello")))
+        .await
+        .is_ok());
+}
+
+#[tokio::main]
+#[test]
+async fn test_rejected_subscription_sends_nothing() {
+    init();
+    // a bare peer taking the SETUP, which never grants a lease.
+    let listener = TcpListener::bind("127.0.0.1:8032").await.unwrap();
+    let accepting = tokio::spawn(async move { listener.accept().await.unwrap().0 });
+    let cli = RSocketFactory::connect()
+        .transport(TcpClientTransport::from("127.0.0.1:8032"))
+        .lease()
+        .start()
+        .await
+        .unwrap();
+    let mut socket = accepting.await.unwrap();
+    assert!(matches!(read(&mut socket).await.get_body(), Body::Setup(_)));
+
+    let mut sub = cli.request_stream_with(Payload::from("hello"), StreamOptions::default());
+    assert!(is_rejected(&sub.next().await.unwrap().unwrap_err()));
+    sub.request(5).unwrap();
+    sub.cancel().unwrap();
+    drop(sub);
+
+    let next = tokio::time::timeout(Duration::from_millis(500), read(&mut socket)).await;
+    assert!(next.is_err(), "unexpected frame: {:?}", next.unwrap());
+}
+
+#[tokio::main]
+#[test]
+async fn test_lease_sees_active_streams() {
+    init();
+    let active = Arc::new(AtomicUsize::new(0));
+    let observed = active.clone();
+    let requester: Arc<Mutex<Option<Box<dyn RSocket>>>> = Arc::default();
+    let accepted = requester.clone();
+    tokio::spawn(async move {
+        RSocketFactory::receive()
+            .transport(TcpServerTransport::from("127.0.0.1:8037"))
+            .acceptor(Box::new(move |_setup, socket| {
+                accepted.lock().unwrap().replace(socket);
+                Ok(Box::new(IdleRSocket))
+            }))
+            .lease(move |n: usize| {
+                observed.store(n, Ordering::SeqCst);
+                Some(Lease::new(Duration::from_millis(1000), 10))
+            })
+            .serve()
+            .await
+    });
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    let cli = RSocketFactory::connect()
+        .acceptor(Box::new(|| Box::new(IdleRSocket)))
+        .transport(TcpClientTransport::from("127.0.0.1:8037"))
+        .lease()
+        .start()
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    // a channel requested by the client and a stream requested by the server.
+    let reqs = futures::stream::iter(vec![Ok(Payload::from("hello"))]);
+    let _channel = cli.request_channel(Box::pin(reqs.chain(futures::stream::pending())));
+    let socket = requester.lock().unwrap().take().unwrap();
+    let _stream = socket.request_stream(Payload::from("hello"));
+
+    // the strategy is asked again once the first lease expires.
+    tokio::time::sleep(Duration::from_millis(1200)).await;
+    assert_eq!(2, active.load(Ordering::SeqCst));
+}
@@ -0,0 +1,48 @@
+use std::time::Duration;
+
+use rsocket_rust::prelude::*;
+use rsocket_rust_transport_tcp::TcpClientTransport;
+use tokio::net::TcpListener;
+
+fn init() {
+    let _ = env_logger::builder()
+        .format_timestamp_millis()
+        .is_test(true)
+        .try_init();
+}
+
+#[tokio::main]
+#[test]
+async fn test_requests_wait_for_queue_capacity() {
+    init();
+    // a peer which accepts connections but never reads from them.
+    let listener = TcpListener::bind("127.0.0.1:7993").await.unwrap();
+    tokio::spawn(async move {
+        let (_socket, _) = listener.accept().await.unwrap();
+        tokio::time::sleep(Duration::from_secs(60)).await;
+    });
+
+    let max_bytes = 256 * 1024;
+    let cli = RSocketFactory::connect()
+        .transport(TcpClientTransport::from("127.0.0.1:7993"))
+        .outbound_queue(QueueOptions::new().max_bytes(max_bytes))
+        .start()
+        .await
+        .unwrap();
+
+    let data = vec![0u8; 64 * 1024];
+    let mut blocked = false;
+    for _ in 0..4096 {
+        let req = Payload::builder().set_data(data.clone()).build();
+        let sent = tokio::time::timeout(Duration::from_millis(200), cli.fire_and_forget(req)).await;
+        if sent.is_err() {
+            blocked = true;
+            break;
+        }
+        assert!(cli.queue_depth().bytes < max_bytes + 2 * data.len());
+    }
+    assert!(blocked, "requests should wait once the queue is full");
+    let depth = cli.queue_depth();
+    assert!(depth.bytes >= max_bytes);
+    assert!(depth.bytes < max_bytes + 2 * data.len());
+}
@@ -0,0 +1,176 @@
+use std::time::Duration;
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use rsocket_rust::error::{ERR_CONN_FAILED, ERR_INVALID, ERR_INVALID_SETUP};
+use rsocket_rust::frame::{self, Body, Frame};
+use rsocket_rust::prelude::*;
+use rsocket_rust::utils::{EchoRSocket, Writeable};
+use rsocket_rust_transport_tcp::TcpServerTransport;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+fn init() {
+    let _ = env_logger::builder()
+        .format_timestamp_millis()
+        .is_test(true)
+        .try_init();
+}
+
+async fn serve(addr: &'static str) {
+    serve_with(addr, ConnectionRegistry::new()).await
+}
+
+async fn serve_with(addr: &'static str, registry: ConnectionRegistry) {
+    tokio::spawn(async move {
+        RSocketFactory::receive()
+            .transport(TcpServerTransport::from(addr))
+            .registry(registry)
+            .acceptor(Box::new(|_setup, _socket| Ok(Box::new(EchoRSocket))))
+            .serve()
+            .await
+    });
+    tokio::time::sleep(Duration::from_millis(300)).await;
+}
+
+fn setup() -> Frame {
+    frame::Setup::builder(0, 0)
+        .set_mime_data("text/plain")
+        .set_mime_metadata("text/plain")
+        .build()
+}
+
+fn request_response(sid: u32) -> Frame {
+    frame::RequestResponse::builder(sid, 0)
+        .set_data(Bytes::from("hello"))
+        .build()
+}
+
+async fn write_raw(socket: &mut TcpStream, raw: &[u8]) {
+    let mut bf = BytesMut::new();
+    bf.put_uint(raw.len() as u64, 3);
+    bf.put_slice(raw);
+    socket.write_all(&bf).await.unwrap();
+}
+
+async fn write(socket: &mut TcpStream, frame: Frame) {
+    let mut bf = BytesMut::new();
+    frame.write_to(&mut bf);
+    write_raw(socket, &bf).await;
+}
+
+async fn read(socket: &mut TcpStream) -> Option<Frame> {
+    let mut len = [0u8; 3];
+    let read = tokio::time::timeout(Duration::from_secs(2), socket.read_exact(&mut len))
+        .await
+        .expect("expect a frame");
+    if read.is_err() {
+        return None;
+    }
+    let len = (&len[..]).get_uint(3) as usize;
+    let mut bf = BytesMut::new();
+    bf.resize(len, 0);
+    socket.read_exact(&mut bf).await.unwrap();
+    Some(Frame::decode(&mut bf).unwrap())
+}
+
+/// Reads the ERROR frame answered by the server, returning its stream id and code.
+async fn read_error(socket: &mut TcpStream) -> (u32, u32) {
+    let frame = read(socket).await.expect("expect an ERROR frame");
+    let sid = frame.get_stream_id();
+    match frame.get_body() {
+        Body::Error(e) => (sid, e.get_code()),
+        other => panic!("expect an ERROR frame, got {:?}", other),
+    }
+}
+
+async fn assert_closed(socket: &mut TcpStream) {
+    assert!(read(socket).await.is_none(), "connection should be closed");
+}
+
+#[tokio::main]
+#[test]
+async fn test_setup_comes_first() {
+    init();
+    serve("127.0.0.1:7996").await;
+    let mut socket = TcpStream::connect("127.0.0.1:7996").await.unwrap();
+    write(&mut socket, request_response(1)).await;
+    assert_eq!((0, ERR_INVALID_SETUP), read_error(&mut socket).await);
+    assert_closed(&mut socket).await;
+
+    let mut socket = TcpStream::connect("127.0.0.1:7996").await.unwrap();
+    write(&mut socket, setup()).await;
+    write(&mut socket, setup()).await;
+    assert_eq!((0, ERR_CONN_FAILED), read_error(&mut socket).await);
+    assert_closed(&mut socket).await;
+}
+
+#[tokio::main]
+#[test]
+async fn test_stream_ids() {
+    init();
+    serve("127.0.0.1:7997").await;
+    // clients open odd streams only.
+    let mut socket = TcpStream::connect("127.0.0.1:7997").await.unwrap();
+    write(&mut socket, setup()).await;
+    write(&mut socket, request_response(2)).await;
+    assert_eq!((0, ERR_CONN_FAILED), read_error(&mut socket).await);
+    assert_closed(&mut socket).await;
+
+    // stream ids are not reused.
+    let mut socket = TcpStream::connect("127.0.0.1:7997").await.unwrap();
+    write(&mut socket, setup()).await;
+    write(&mut socket, request_response(1)).await;
+    let response = read(&mut socket).await.unwrap();
+    assert!(matches!(response.get_body_ref(), Body::Payload(_)));
+    write(&mut socket, request_response(1)).await;
+    assert_eq!((1, ERR_INVALID), read_error(&mut socket).await);
+
+    // frames of terminated streams are ignored.
+    write(&mut socket, frame::RequestN::builder(1, 0).set_n(1).build()).await;
+    write(&mut socket, request_response(3)).await;
+    let response = read(&mut socket).await.unwrap();
+    assert_eq!(3, response.get_stream_id());
+}
+
+#[tokio::main]
+#[test]
+async fn test_unknown_frames() {
+    init();
+    serve("127.0.0.1:7998").await;
+    let mut socket = TcpStream::connect("127.0.0.1:7998").await.unwrap();
+    write(&mut socket, setup()).await;
+
+    // an unknown frame type which may be ignored.
+    let mut raw = BytesMut::new();
+    raw.put_u32(0);
+    raw.put_u16((0x30 << 10) | Frame::FLAG_IGNORE);
+    raw.put_slice(b"extension");
+    write_raw(&mut socket, &raw).await;
+    write(&mut socket, request_response(1)).await;
+    let response = read(&mut socket).await.unwrap();
+    assert_eq!(1, response.get_stream_id());
+
+    // an unknown frame type which must be understood.
+    let mut raw = BytesMut::new();
+    raw.put_u32(0);
+    raw.put_u16(0x30 << 10);
+    write_raw(&mut socket, &raw).await;
+    assert_eq!((0, ERR_CONN_FAILED), read_error(&mut socket).await);
+    assert_closed(&mut socket).await;
+}
+
+#[tokio::main]
+#[test]
+async fn test_disconnect_tells_peer() {
+    init();
+    let registry = ConnectionRegistry::new();
+    serve_with("127.0.0.1:8028", registry.clone()).await;
+    let mut socket = TcpStream::connect("127.0.0.1:8028").await.unwrap();
+    write(&mut socket, setup()).await;
+    write(&mut socket, request_response(1)).await;
+    assert_eq!(1, read(&mut socket).await.unwrap().get_stream_id());
+
+    assert!(registry.disconnect(registry.connections()[0].id()));
+    assert_eq!((0, ERR_CONN_FAILED), read_error(&mut socket).await);
+    assert_closed(&mut socket).await;
+}
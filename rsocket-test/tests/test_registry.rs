@@ -0,0 +1,123 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use rsocket_rust::prelude::*;
+use rsocket_rust::{async_trait, Client, Result};
+use rsocket_rust_transport_tcp::{TcpClientTransport, TcpServerTransport};
+
+fn init() {
+    let _ = env_logger::builder()
+        .format_timestamp_millis()
+        .is_test(true)
+        .try_init();
+}
+
+/// Client responder recording what the server pushed to it.
+#[derive(Clone, Default)]
+struct Inbox {
+    received: Arc<Mutex<Vec<String>>>,
+}
+
+#[async_trait]
+impl RSocket for Inbox {
+    async fn metadata_push(&self, req: Payload) -> Result<()> {
+        let it = String::from_utf8_lossy(req.metadata().unwrap()).to_string();
+        self.received.lock().unwrap().push(it);
+        Ok(())
+    }
+
+    async fn fire_and_forget(&self, req: Payload) -> Result<()> {
+        let it = req.data_utf8().unwrap().to_string();
+        self.received.lock().unwrap().push(it);
+        Ok(())
+    }
+
+    async fn request_response(&self, req: Payload) -> Result<Option<Payload>> {
+        Ok(Some(req))
+    }
+
+    fn request_stream(&self, _req: Payload) -> Flux<Result<Payload>> {
+        Box::pin(futures::stream::empty())
+    }
+
+    fn request_channel(&self, reqs: Flux<Result<Payload>>) -> Flux<Result<Payload>> {
+        reqs
+    }
+}
+
+async fn connect(user: &str, inbox: Inbox, closed: Arc<AtomicBool>) -> Client {
+    RSocketFactory::connect()
+        .transport(TcpClientTransport::from("127.0.0.1:8018"))
+        .setup(Payload::builder().set_data_utf8(user).build())
+        .acceptor(Box::new(move || Box::new(inbox.clone())))
+        // pushes get recorded in the order they were sent.
+        .dispatch_options(DispatchOptions::new().ordered(true))
+        .on_close(Box::new(move || closed.store(true, Ordering::SeqCst)))
+        .start()
+        .await
+        .unwrap()
+}
+
+#[tokio::main]
+#[test]
+async fn test_connection_registry() {
+    init();
+    let registry = ConnectionRegistry::new();
+    let tagging = registry.clone();
+    let serving = registry.clone();
+    tokio::spawn(async move {
+        RSocketFactory::receive()
+            .transport(TcpServerTransport::from("127.0.0.1:8018"))
+            .registry(serving)
+            .acceptor(Box::new(move |setup, _socket| {
+                let user = String::from_utf8_lossy(setup.data().unwrap());
+                assert!(tagging.tag(setup.connection_id().unwrap(), format!("user:{}", user)));
+                Ok(Box::new(rsocket_rust::utils::EchoRSocket))
+            }))
+            .serve()
+            .await
+    });
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    let (alice, bob) = (Inbox::default(), Inbox::default());
+    let (alice_closed, bob_closed) = (Arc::default(), Arc::default());
+    let _alice = connect("alice", alice.clone(), Arc::clone(&alice_closed)).await;
+    let _bob = connect("bob", bob.clone(), Arc::clone(&bob_closed)).await;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(2, registry.len());
+
+    let found = registry.find_by_tag("user:alice");
+    assert_eq!(1, found.len());
+    let id = found[0].id();
+    assert_eq!(vec!["user:alice"], found[0].tags());
+    assert!(found[0].connection_info().unwrap().remote_addr().is_some());
+
+    // the server initiates requests to a specific client.
+    let res = registry
+        .get(id)
+        .unwrap()
+        .requester()
+        .request_response(Payload::from("ping"))
+        .await
+        .unwrap();
+    assert_eq!(Some("ping"), res.unwrap().data_utf8());
+
+    let sent = registry
+        .broadcast_fire_and_forget(Payload::from("mail"), |it| it.has_tag("user:alice"))
+        .await;
+    assert_eq!(1, sent);
+    let notice = Payload::builder().set_metadata_utf8("notice").build();
+    assert_eq!(2, registry.broadcast_metadata_push(notice, |_| true).await);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(vec!["mail", "notice"], *alice.received.lock().unwrap());
+    assert_eq!(vec!["notice"], *bob.received.lock().unwrap());
+
+    assert!(registry.disconnect(id));
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(alice_closed.load(Ordering::SeqCst));
+    assert!(!bob_closed.load(Ordering::SeqCst));
+    assert_eq!(1, registry.len());
+    assert!(registry.get(id).is_none());
+    assert!(registry.find_by_tag("user:bob")[0].id() != id);
+}
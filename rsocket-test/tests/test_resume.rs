@@ -0,0 +1,226 @@
+#[macro_use]
+extern crate log;
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use rsocket_rust::prelude::*;
+use rsocket_rust::utils::EchoRSocket;
+use rsocket_rust::{async_trait, stream, Client, Result};
+use rsocket_rust_transport_tcp::{TcpClientTransport, TcpServerTransport};
+use tokio::io::copy_bidirectional;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Notify;
+
+fn init() {
+    let _ = env_logger::builder()
+        .format_timestamp_millis()
+        .is_test(true)
+        .try_init();
+}
+
+/// Responder emitting a slow stream of numbered payloads.
+struct TickRSocket;
+
+#[async_trait]
+impl RSocket for TickRSocket {
+    async fn metadata_push(&self, _req: Payload) -> Result<()> {
+        Ok(())
+    }
+
+    async fn fire_and_forget(&self, _req: Payload) -> Result<()> {
+        Ok(())
+    }
+
+    async fn request_response(&self, req: Payload) -> Result<Option<Payload>> {
+        Ok(Some(req))
+    }
+
+    fn request_stream(&self, _req: Payload) -> Flux<Result<Payload>> {
+        Box::pin(stream! {
+            for i in 0..40 {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                yield Ok(Payload::builder().set_data_utf8(&i.to_string()).build());
+            }
+        })
+    }
+
+    fn request_channel(&self, reqs: Flux<Result<Payload>>) -> Flux<Result<Payload>> {
+        reqs
+    }
+}
+
+/// Forwards connections to `upstream`, dropping all of them each time `cut` is notified.
+async fn proxy(addr: &'static str, upstream: &'static str, cut: Arc<Notify>) {
+    let listener = TcpListener::bind(addr).await.unwrap();
+    tokio::spawn(async move {
+        while let Ok((mut inbound, _)) = listener.accept().await {
+            let cut = cut.clone();
+            tokio::spawn(async move {
+                let mut outbound = TcpStream::connect(upstream).await.unwrap();
+                tokio::select! {
+                    _ = copy_bidirectional(&mut inbound, &mut outbound) => {}
+                    _ = cut.notified() => info!("connection cut"),
+                }
+            });
+        }
+    });
+}
+
+/// Forwards connections to `upstream`, each time `rebind` is notified the client side of them
+/// gets dropped while the server side stays open, as after a NAT rebinding.
+async fn rebinding_proxy(addr: &'static str, upstream: &'static str, rebind: Arc<Notify>) {
+    let listener = TcpListener::bind(addr).await.unwrap();
+    tokio::spawn(async move {
+        while let Ok((mut inbound, _)) = listener.accept().await {
+            let rebind = rebind.clone();
+            tokio::spawn(async move {
+                let mut outbound = TcpStream::connect(upstream).await.unwrap();
+                tokio::select! {
+                    _ = copy_bidirectional(&mut inbound, &mut outbound) => return,
+                    _ = rebind.notified() => info!("connection rebound"),
+                }
+                drop(inbound);
+                // the server is not told, its connection stays open.
+                let _upstream = outbound;
+                std::future::pending::<()>().await
+            });
+        }
+    });
+}
+
+async fn serve(addr: &'static str, resume: bool) {
+    tokio::spawn(async move {
+        let mut builder = RSocketFactory::receive()
+            .transport(TcpServerTransport::from(addr))
+            .acceptor(Box::new(|setup, _socket| {
+                info!("accept setup: {:?}", setup);
+                Ok(Box::new(TickRSocket))
+            }));
+        if resume {
+            builder = builder.resume(ResumeOptions::new());
+        }
+        builder.serve().await
+    });
+    tokio::time::sleep(Duration::from_millis(300)).await;
+}
+
+async fn connect(addr: &'static str) -> Client {
+    let opts = ResumeOptions::new().reconnect_interval(Duration::from_millis(100));
+    RSocketFactory::connect()
+        .acceptor(Box::new(|| Box::new(EchoRSocket)))
+        .transport(TcpClientTransport::from(addr))
+        .resume(opts, move || TcpClientTransport::from(addr))
+        .start()
+        .await
+        .unwrap()
+}
+
+#[tokio::main]
+#[test]
+async fn test_resume_stream_across_reconnect() {
+    init();
+    let cut = Arc::new(Notify::new());
+    serve("127.0.0.1:7982", true).await;
+    proxy("127.0.0.1:7983", "127.0.0.1:7982", cut.clone()).await;
+    let cli = connect("127.0.0.1:7983").await;
+
+    let mut results = cli.request_stream(Payload::from("start"));
+    for i in 0..40 {
+        let next = results.next().await.unwrap().unwrap();
+        assert_eq!(Some(format!("{}", i).as_str()), next.data_utf8());
+        if i == 5 || i == 20 {
+            cut.notify_waiters();
+        }
+    }
+    assert!(results.next().await.is_none());
+
+    let res = cli
+        .request_response(Payload::from("after resume"))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(Some("after resume"), res.data_utf8());
+}
+
+#[tokio::main]
+#[test]
+async fn test_resume_unsupported() {
+    init();
+    serve("127.0.0.1:7984", false).await;
+    let cli = connect("127.0.0.1:7984").await;
+    // the setup is refused and resuming the session gets rejected as well.
+    let closed = tokio::time::timeout(Duration::from_secs(3), cli.wait_for_close()).await;
+    assert!(closed.is_ok());
+}
+
+#[tokio::main]
+#[test]
+async fn test_resume_connected_session() {
+    init();
+    let rebind = Arc::new(Notify::new());
+    serve("127.0.0.1:8027", true).await;
+    rebinding_proxy("127.0.0.1:8033", "127.0.0.1:8027", rebind.clone()).await;
+    let cli = connect("127.0.0.1:8033").await;
+
+    // the server still holds the previous connection when the client resumes the session.
+    let mut results = cli.request_stream(Payload::from("start"));
+    for i in 0..40 {
+        let next = results.next().await.unwrap().unwrap();
+        assert_eq!(Some(format!("{}", i).as_str()), next.data_utf8());
+        if i == 5 {
+            rebind.notify_waiters();
+        }
+    }
+    assert!(results.next().await.is_none());
+
+    let res = cli
+        .request_response(Payload::from("still here"))
+        .await
+        .unwrap();
+    assert_eq!(Some("still here"), res.unwrap().data_utf8());
+}
+
+#[tokio::main]
+#[test]
+async fn test_expired_session_fails_pending_requests() {
+    init();
+    let requester: Arc<Mutex<Option<Box<dyn RSocket>>>> = Arc::default();
+    let accepted = requester.clone();
+    tokio::spawn(async move {
+        RSocketFactory::receive()
+            .transport(TcpServerTransport::from("127.0.0.1:8034"))
+            .acceptor(Box::new(move |_setup, socket| {
+                accepted.lock().unwrap().replace(socket);
+                Ok(Box::new(TickRSocket))
+            }))
+            .resume(ResumeOptions::new().session_duration(Duration::from_millis(300)))
+            .serve()
+            .await
+    });
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    let cut = Arc::new(Notify::new());
+    proxy("127.0.0.1:8035", "127.0.0.1:8034", cut.clone()).await;
+
+    // nothing listens where the client reconnects, the session is left to expire.
+    let opts = ResumeOptions::new().reconnect_interval(Duration::from_millis(100));
+    let _cli = RSocketFactory::connect()
+        .acceptor(Box::new(|| Box::new(EchoRSocket)))
+        .transport(TcpClientTransport::from("127.0.0.1:8035"))
+        .resume(opts, || TcpClientTransport::from("127.0.0.1:8036"))
+        .start()
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let requester = requester.lock().unwrap().take().unwrap();
+    cut.notify_waiters();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let res = tokio::time::timeout(
+        Duration::from_secs(3),
+        requester.request_response(Payload::from("hello")),
+    )
+    .await
+    .expect("the request outlives its session");
+    assert!(res.is_err());
+}
@@ -0,0 +1,96 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::{stream, StreamExt};
+use rsocket_rust::prelude::*;
+use rsocket_rust::{async_trait, Result};
+use rsocket_rust_transport_tcp::{TcpClientTransport, TcpServerTransport};
+
+const BULK_SIZE: usize = 2000;
+
+fn init() {
+    let _ = env_logger::builder()
+        .format_timestamp_millis()
+        .is_test(true)
+        .try_init();
+}
+
+/// Responder streaming large payloads while answering small requests.
+struct Bulk;
+
+#[async_trait]
+impl RSocket for Bulk {
+    async fn metadata_push(&self, _req: Payload) -> Result<()> {
+        Ok(())
+    }
+
+    async fn fire_and_forget(&self, _req: Payload) -> Result<()> {
+        Ok(())
+    }
+
+    async fn request_response(&self, req: Payload) -> Result<Option<Payload>> {
+        Ok(Some(req))
+    }
+
+    fn request_stream(&self, _req: Payload) -> Flux<Result<Payload>> {
+        if let Some(ctx) = RequestContext::current() {
+            ctx.set_weight(4);
+        }
+        let data = vec![b'x'; 64 * 1024];
+        Box::pin(
+            stream::iter(0..BULK_SIZE)
+                .map(move |_| Ok(Payload::builder().set_data(data.clone()).build())),
+        )
+    }
+
+    fn request_channel(&self, reqs: Flux<Result<Payload>>) -> Flux<Result<Payload>> {
+        reqs
+    }
+}
+
+#[tokio::main]
+#[test]
+async fn test_interleave_streams() {
+    init();
+    tokio::spawn(async move {
+        RSocketFactory::receive()
+            .transport(TcpServerTransport::from("127.0.0.1:8025"))
+            .acceptor(Box::new(|_setup, _socket| Ok(Box::new(Bulk))))
+            .serve()
+            .await
+    });
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    let cli = RSocketFactory::connect()
+        .transport(TcpClientTransport::from("127.0.0.1:8025"))
+        .start()
+        .await
+        .unwrap();
+
+    let received = Arc::new(AtomicUsize::new(0));
+    let counting = received.clone();
+    let opts = StreamOptions::new().initial_request_n(BULK_SIZE as u32);
+    let mut bulk = cli.request_stream_with(Payload::from("bulk"), opts);
+    let consumer = tokio::spawn(async move {
+        while let Some(next) = bulk.next().await {
+            next.unwrap();
+            counting.fetch_add(1, Ordering::SeqCst);
+        }
+    });
+    while received.load(Ordering::SeqCst) == 0 {
+        tokio::time::sleep(Duration::from_millis(1)).await;
+    }
+
+    // the reply does not wait for the frames the bulk stream has queued before it.
+    let res = cli.request_response(Payload::from("ping")).await.unwrap();
+    assert_eq!(Some("ping"), res.unwrap().data_utf8());
+    let done = received.load(Ordering::SeqCst);
+    assert!(
+        done < BULK_SIZE / 2,
+        "reply came after {} bulk payloads",
+        done
+    );
+
+    consumer.await.unwrap();
+    assert_eq!(BULK_SIZE, received.load(Ordering::SeqCst));
+}
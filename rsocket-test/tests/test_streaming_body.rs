@@ -0,0 +1,297 @@
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures::stream;
+use rsocket_rust::prelude::*;
+use rsocket_rust::{async_trait, Result};
+use rsocket_rust_transport_tcp::{TcpClientTransport, TcpServerTransport};
+use tokio::sync::Notify;
+
+fn init() {
+    let _ = env_logger::builder()
+        .format_timestamp_millis()
+        .is_test(true)
+        .try_init();
+}
+
+fn artifact(size: usize) -> Vec<u8> {
+    (0..size).map(|it| (it % 251) as u8).collect()
+}
+
+/// Metadata, number of chunks and data of the requests received.
+type Received = Vec<(Option<Bytes>, usize, Vec<u8>)>;
+
+/// Responder summing up the data it receives, streamed or not.
+///
+/// Requests with the metadata `hold` are not read until the gate is opened.
+#[derive(Clone, Default)]
+struct Sink {
+    received: Arc<Mutex<Received>>,
+    gate: Arc<Notify>,
+}
+
+impl Sink {
+    /// Reads the data of a request, returning the number of chunks it came in.
+    async fn receive(&self, req: Payload) -> Result<usize> {
+        let (data, metadata) = req.split();
+        if metadata.as_deref() == Some(&b"hold"[..]) {
+            self.gate.notified().await;
+        }
+        let mut chunks = 0;
+        let mut received = data.map(|it| it.to_vec()).unwrap_or_default();
+        if let Some(mut body) = RequestContext::current().and_then(|it| it.take_body()) {
+            assert!(received.is_empty(), "data comes along with the body");
+            while let Some(chunk) = body.next().await {
+                received.extend_from_slice(&chunk?);
+                chunks += 1;
+            }
+        }
+        self.received
+            .lock()
+            .unwrap()
+            .push((metadata, chunks, received));
+        Ok(chunks)
+    }
+}
+
+#[async_trait]
+impl RSocket for Sink {
+    async fn metadata_push(&self, _req: Payload) -> Result<()> {
+        Ok(())
+    }
+
+    async fn fire_and_forget(&self, req: Payload) -> Result<()> {
+        self.receive(req).await.map(|_| ())
+    }
+
+    async fn request_response(&self, req: Payload) -> Result<Option<Payload>> {
+        let chunks = self.receive(req).await?;
+        Ok(Some(
+            Payload::builder()
+                .set_data_utf8(&chunks.to_string())
+                .build(),
+        ))
+    }
+
+    fn request_stream(&self, req: Payload) -> Flux<Result<Payload>> {
+        let (data, metadata) = req.split();
+        let data = data.map(|it| it.to_vec()).unwrap_or_default();
+        self.received.lock().unwrap().push((metadata, 0, data));
+        Box::pin(stream::empty())
+    }
+
+    fn request_channel(&self, reqs: Flux<Result<Payload>>) -> Flux<Result<Payload>> {
+        reqs
+    }
+}
+
+async fn serve(addr: &'static str, opts: ReassemblyOptions) -> Sink {
+    let sink = Sink::default();
+    let responder = sink.clone();
+    tokio::spawn(async move {
+        RSocketFactory::receive()
+            .transport(TcpServerTransport::from(addr))
+            .reassembly_options(opts)
+            .acceptor(Box::new(move |_setup, _socket| {
+                Ok(Box::new(responder.clone()))
+            }))
+            .serve()
+            .await
+    });
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    sink
+}
+
+#[tokio::main]
+#[test]
+async fn test_stream_request_body() {
+    init();
+    let sink = serve(
+        "127.0.0.1:8023",
+        ReassemblyOptions::new().stream_bodies(true),
+    )
+    .await;
+    let cli = RSocketFactory::connect()
+        .transport(TcpClientTransport::from("127.0.0.1:8023"))
+        .fragment(1024)
+        .start()
+        .await
+        .unwrap();
+
+    let data = artifact(1024 * 1024);
+    let metadata = Bytes::from(vec![b'm'; 3000]);
+    let res = cli
+        .request_response_from(Some(metadata.clone()), Cursor::new(data.clone()))
+        .await
+        .unwrap()
+        .unwrap();
+    let chunks: usize = res.data_utf8().unwrap().parse().unwrap();
+    assert!(chunks > 1000, "data came in {} chunks", chunks);
+
+    // a body without data still ends.
+    cli.fire_and_forget_from(Some(metadata.clone()), Cursor::new(Vec::new()))
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    let received = sink.received.lock().unwrap();
+    assert_eq!(2, received.len());
+    assert_eq!(Some(metadata.clone()), received[0].0);
+    assert_eq!(data, received[0].2);
+    assert_eq!(Some(metadata), received[1].0);
+    assert!(received[1].2.is_empty());
+}
+
+#[tokio::main]
+#[test]
+async fn test_reassemble_request_body() {
+    init();
+    let sink = serve("127.0.0.1:8024", ReassemblyOptions::new()).await;
+    let cli = RSocketFactory::connect()
+        .transport(TcpClientTransport::from("127.0.0.1:8024"))
+        .start()
+        .await
+        .unwrap();
+
+    // without an mtu, the body gets cut into chunks of 64KiB.
+    let data = artifact(200 * 1024);
+    cli.fire_and_forget_from(None, Cursor::new(data.clone()))
+        .await
+        .unwrap();
+    let res = cli
+        .request_response_from(Some(Bytes::from("small")), Cursor::new(b"hello".to_vec()))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(Some("0"), res.data_utf8());
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    // fire_and_forget is handled apart, it may complete last.
+    let mut received = sink.received.lock().unwrap().clone();
+    received.sort_by_key(|it| it.0.is_some());
+    assert_eq!(2, received.len());
+    assert_eq!((None, 0), (received[0].0.clone(), received[0].1));
+    assert_eq!(data, received[0].2);
+    assert_eq!(Some(Bytes::from("small")), received[1].0);
+    assert_eq!(b"hello".to_vec(), received[1].2);
+}
+
+#[tokio::main]
+#[test]
+async fn test_unconsumed_body_does_not_hold_back_connection() {
+    init();
+    let sink = serve(
+        "127.0.0.1:8029",
+        ReassemblyOptions::new().stream_bodies(true),
+    )
+    .await;
+    let cli = RSocketFactory::connect()
+        .transport(TcpClientTransport::from("127.0.0.1:8029"))
+        .fragment(1024)
+        .start()
+        .await
+        .unwrap();
+
+    let data = artifact(1024 * 1024);
+    let uploading = {
+        let (cli, data) = (cli.clone(), data.clone());
+        tokio::spawn(async move {
+            cli.request_response_from(Some(Bytes::from("hold")), Cursor::new(data))
+                .await
+        })
+    };
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    // the body is buffered while the responder does not read it, other requests go on.
+    let res = tokio::time::timeout(
+        Duration::from_secs(5),
+        cli.request_response(Payload::from("ping")),
+    )
+    .await
+    .expect("request blocked by an unconsumed body")
+    .unwrap();
+    assert_eq!(Some("0"), res.unwrap().data_utf8());
+
+    sink.gate.notify_one();
+    let res = uploading.await.unwrap().unwrap().unwrap();
+    let chunks: usize = res.data_utf8().unwrap().parse().unwrap();
+    assert!(chunks > 1000, "data came in {} chunks", chunks);
+    assert_eq!(data, sink.received.lock().unwrap()[1].2);
+}
+
+#[tokio::main]
+#[test]
+async fn test_slow_body_consumer() {
+    init();
+    let opts = ReassemblyOptions::new()
+        .stream_bodies(true)
+        .max_payload_size(64 * 1024);
+    let sink = serve("127.0.0.1:8030", opts).await;
+    let cli = RSocketFactory::connect()
+        .transport(TcpClientTransport::from("127.0.0.1:8030"))
+        .fragment(1024)
+        .start()
+        .await
+        .unwrap();
+
+    // the body buffers far less than the upload, which waits for the responder to read it.
+    let data = artifact(1024 * 1024);
+    let uploading = {
+        let (cli, data) = (cli.clone(), data.clone());
+        tokio::spawn(async move {
+            cli.request_response_from(Some(Bytes::from("hold")), Cursor::new(data))
+                .await
+        })
+    };
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert!(!uploading.is_finished());
+    assert!(sink.received.lock().unwrap().is_empty());
+
+    sink.gate.notify_one();
+    let res = tokio::time::timeout(Duration::from_secs(10), uploading)
+        .await
+        .expect("upload stalled")
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    let chunks: usize = res.data_utf8().unwrap().parse().unwrap();
+    assert!(chunks > 1000, "data came in {} chunks", chunks);
+    assert_eq!(data, sink.received.lock().unwrap()[0].2);
+
+    let res = cli.request_response(Payload::from("ping")).await.unwrap();
+    assert_eq!(Some("0"), res.unwrap().data_utf8());
+}
+
+#[tokio::main]
+#[test]
+async fn test_stream_request_reassembled() {
+    init();
+    let sink = serve(
+        "127.0.0.1:8031",
+        ReassemblyOptions::new().stream_bodies(true),
+    )
+    .await;
+    let cli = RSocketFactory::connect()
+        .transport(TcpClientTransport::from("127.0.0.1:8031"))
+        .fragment(1024)
+        .start()
+        .await
+        .unwrap();
+
+    // only request-response and fire-and-forget get their data streamed.
+    let data = artifact(10 * 1024);
+    let metadata = Bytes::from(vec![b'm'; 3000]);
+    let req = Payload::builder()
+        .set_data(data.clone())
+        .set_metadata(metadata.clone())
+        .build();
+    let mut results = cli.request_stream(req);
+    assert!(results.next().await.is_none());
+
+    let received = sink.received.lock().unwrap();
+    assert_eq!(1, received.len());
+    assert_eq!((Some(metadata), 0, data), received[0]);
+}
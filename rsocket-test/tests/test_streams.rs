@@ -0,0 +1,170 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::stream;
+use rsocket_rust::prelude::*;
+use rsocket_rust::{async_trait, Client, Result};
+use rsocket_rust_transport_tcp::{TcpClientTransport, TcpServerTransport};
+
+fn init() {
+    let _ = env_logger::builder()
+        .format_timestamp_millis()
+        .is_test(true)
+        .try_init();
+}
+
+/// Counts the responder work which got dropped before it finished.
+struct Unfinished(Option<Arc<AtomicUsize>>);
+
+impl Unfinished {
+    fn finish(mut self) {
+        self.0 = None;
+    }
+}
+
+impl Drop for Unfinished {
+    fn drop(&mut self) {
+        if let Some(it) = &self.0 {
+            it.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+}
+
+/// Responder whose streams emit a single payload and never complete.
+#[derive(Clone, Default)]
+struct Endless {
+    unfinished: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl RSocket for Endless {
+    async fn metadata_push(&self, _req: Payload) -> Result<()> {
+        Ok(())
+    }
+
+    async fn fire_and_forget(&self, _req: Payload) -> Result<()> {
+        Ok(())
+    }
+
+    async fn request_response(&self, req: Payload) -> Result<Option<Payload>> {
+        let guard = Unfinished(Some(self.unfinished.clone()));
+        if req.data_utf8() == Some("slow") {
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+        guard.finish();
+        Ok(Some(req))
+    }
+
+    fn request_stream(&self, req: Payload) -> Flux<Result<Payload>> {
+        let guard = Unfinished(Some(self.unfinished.clone()));
+        Box::pin(
+            stream::once(async move { Ok(req) })
+                .chain(stream::pending())
+                .map(move |it| {
+                    let _ = &guard;
+                    it
+                }),
+        )
+    }
+
+    fn request_channel(&self, _reqs: Flux<Result<Payload>>) -> Flux<Result<Payload>> {
+        Box::pin(stream::once(async { Ok(Payload::from("first")) }).chain(stream::pending()))
+    }
+}
+
+async fn connect(addr: &'static str, responder: Endless) -> Client {
+    tokio::spawn(async move {
+        RSocketFactory::receive()
+            .transport(TcpServerTransport::from(addr))
+            .acceptor(Box::new(move |_setup, _socket| {
+                Ok(Box::new(responder.clone()))
+            }))
+            .serve()
+            .await
+    });
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    RSocketFactory::connect()
+        .transport(TcpClientTransport::from(addr))
+        .start()
+        .await
+        .unwrap()
+}
+
+#[tokio::main]
+#[test]
+async fn test_cancelled_stream_is_released() {
+    init();
+    let cli = connect("127.0.0.1:8000", Endless::default()).await;
+
+    let mut subscription = cli.request_stream_with(Payload::from("hello"), StreamOptions::new());
+    assert!(subscription.next().await.unwrap().is_ok());
+    let streams = cli.active_streams();
+    assert_eq!(1, streams.len());
+    assert_eq!(subscription.stream_id(), streams[0].stream_id);
+    assert_eq!(InteractionType::RequestStream, streams[0].interaction);
+    assert_eq!(StreamState::Active, streams[0].state);
+    assert!(streams[0].requester);
+
+    // the responder never completes, cancelling must release the stream anyway.
+    subscription.cancel().unwrap();
+    assert!(cli.active_streams().is_empty());
+
+    cli.request_response(Payload::from("hello")).await.unwrap();
+    assert!(cli.active_streams().is_empty());
+}
+
+#[tokio::main]
+#[test]
+async fn test_half_closed_channel() {
+    init();
+    let cli = connect("127.0.0.1:8001", Endless::default()).await;
+
+    let inputs: Flux<Result<Payload>> = Box::pin(stream::iter(vec![Ok(Payload::from("hello"))]));
+    let mut outputs = cli.request_channel(inputs);
+    assert!(outputs.next().await.unwrap().is_ok());
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // our side completed, the responder keeps its side open.
+    let streams = cli.active_streams();
+    assert_eq!(1, streams.len());
+    assert_eq!(InteractionType::RequestChannel, streams[0].interaction);
+    assert_eq!(StreamState::HalfClosedLocal, streams[0].state);
+    assert!(streams[0].age >= Duration::from_millis(100));
+}
+
+#[tokio::main]
+#[test]
+async fn test_dropped_request_response_is_cancelled() {
+    init();
+    let responder = Endless::default();
+    let cli = connect("127.0.0.1:8005", responder.clone()).await;
+
+    let res = tokio::time::timeout(
+        Duration::from_millis(100),
+        cli.request_response(Payload::from("slow")),
+    )
+    .await;
+    assert!(res.is_err());
+    assert!(cli.active_streams().is_empty());
+
+    // the responder stops working on it.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(1, responder.unfinished.load(Ordering::SeqCst));
+}
+
+#[tokio::main]
+#[test]
+async fn test_dropped_stream_is_cancelled() {
+    init();
+    let responder = Endless::default();
+    let cli = connect("127.0.0.1:8006", responder.clone()).await;
+
+    let mut results = cli.request_stream(Payload::from("hello"));
+    assert!(results.next().await.unwrap().is_ok());
+    drop(results);
+    assert!(cli.active_streams().is_empty());
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(1, responder.unfinished.load(Ordering::SeqCst));
+}
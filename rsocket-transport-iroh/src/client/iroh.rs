@@ -32,7 +32,11 @@ impl Transport for IrohClientTransport {
                     .map_err(|e| RSocketError::Other(e.into()))?;
                 
                 log::info!("✅ Bidirectional stream opened successfully");
-                Ok(IrohConnectionWithStreams::new(send_stream, recv_stream))
+                Ok(IrohConnectionWithStreams::from_connection(
+                    &connection,
+                    send_stream,
+                    recv_stream,
+                ))
             }
             Connector::DirectWithStreams(connection) => {
                 log::info!("✅ Using pre-opened Iroh connection with streams");
@@ -60,7 +64,11 @@ impl Transport for IrohClientTransport {
                     .map_err(|e| RSocketError::Other(e.into()))?;
                 
                 log::info!("✅ Bidirectional stream opened successfully");
-                Ok(IrohConnectionWithStreams::new(send_stream, recv_stream))
+                Ok(IrohConnectionWithStreams::from_connection(
+                    &connection,
+                    send_stream,
+                    recv_stream,
+                ))
             }
             Connector::NodeAddr(node_addr) => {
                 let config = IrohConfig::default();
@@ -79,7 +87,11 @@ impl Transport for IrohClientTransport {
                     .map_err(|e| RSocketError::Other(e.into()))?;
                 
                 log::info!("✅ Bidirectional stream opened successfully");
-                Ok(IrohConnectionWithStreams::new(send_stream, recv_stream))
+                Ok(IrohConnectionWithStreams::from_connection(
+                    &connection,
+                    send_stream,
+                    recv_stream,
+                ))
             }
         }
     }
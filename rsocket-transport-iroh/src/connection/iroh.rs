@@ -1,10 +1,14 @@
 use futures::{SinkExt, StreamExt};
 use iroh::endpoint::Connection;
 use rsocket_rust::error::RSocketError;
-use rsocket_rust::transport::{Connection as RSocketConnection, FrameSink, FrameStream};
-use tokio_util::codec::Framed;
+use rsocket_rust::transport::{
+    Connection as RSocketConnection, ConnectionInfo, FrameSink, FrameStream, PeerIdentity,
+    TransportKind,
+};
+use rsocket_rust::utils::LengthBasedFrameWriter;
 use std::pin::Pin;
 use std::task::{Context, Poll};
+use tokio_util::codec::FramedRead;
 
 use super::codec::LengthBasedFrameCodec;
 
@@ -19,6 +23,15 @@ impl IrohConnection {
     }
 }
 
+/// Identifies the peer of a connection by its NodeId.
+fn node_info(connection: &Connection) -> ConnectionInfo {
+    let identity = connection
+        .remote_node_id()
+        .ok()
+        .map(|it| PeerIdentity::Node(it.to_string()));
+    ConnectionInfo::new(TransportKind::Iroh).set_identity(identity)
+}
+
 #[derive(Debug)]
 pub struct IrohBiStream {
     send_stream: iroh::endpoint::SendStream,
@@ -124,6 +137,10 @@ impl tokio::io::AsyncWrite for IrohBiStream {
 }
 
 impl RSocketConnection for IrohConnection {
+    fn info(&self) -> Option<ConnectionInfo> {
+        Some(node_info(&self.connection))
+    }
+
     fn split(self) -> (Box<FrameSink>, Box<FrameStream>) {
         let connection = self.connection;
         let rt = tokio::runtime::Handle::current();
@@ -138,7 +155,9 @@ impl RSocketConnection for IrohConnection {
         log::info!("✅ Opened bidirectional stream for RSocket frames");
         
         let bi_stream = IrohBiStream::new(send_stream, recv_stream);
-        let (sink, stream) = Framed::new(bi_stream, LengthBasedFrameCodec).split();
+        let (read, write) = tokio::io::split(bi_stream);
+        let sink = LengthBasedFrameWriter::new(write);
+        let stream = FramedRead::new(read, LengthBasedFrameCodec);
         
         let enhanced_sink = sink.sink_map_err(|e| {
             log::error!("❌ RSocket frame sink error: {:?}", e);
@@ -168,21 +187,41 @@ impl RSocketConnection for IrohConnection {
 #[derive(Debug)]
 pub struct IrohConnectionWithStreams {
     bi_stream: IrohBiStream,
+    info: Option<ConnectionInfo>,
 }
 
 impl IrohConnectionWithStreams {
     pub fn new(send_stream: iroh::endpoint::SendStream, recv_stream: iroh::endpoint::RecvStream) -> Self {
         Self {
             bi_stream: IrohBiStream::new(send_stream, recv_stream),
+            info: None,
+        }
+    }
+
+    /// Wraps the streams opened on a connection, along with the NodeId of its peer.
+    pub fn from_connection(
+        connection: &Connection,
+        send_stream: iroh::endpoint::SendStream,
+        recv_stream: iroh::endpoint::RecvStream,
+    ) -> Self {
+        Self {
+            bi_stream: IrohBiStream::new(send_stream, recv_stream),
+            info: Some(node_info(connection)),
         }
     }
 }
 
 impl RSocketConnection for IrohConnectionWithStreams {
+    fn info(&self) -> Option<ConnectionInfo> {
+        self.info.clone()
+    }
+
     fn split(self) -> (Box<FrameSink>, Box<FrameStream>) {
         log::info!("✅ Splitting pre-opened Iroh bidirectional stream for RSocket frames");
         
-        let (sink, stream) = Framed::new(self.bi_stream, LengthBasedFrameCodec).split();
+        let (read, write) = tokio::io::split(self.bi_stream);
+        let sink = LengthBasedFrameWriter::new(write);
+        let stream = FramedRead::new(read, LengthBasedFrameCodec);
         
         let enhanced_sink = sink.sink_map_err(|e| {
             log::error!("❌ RSocket frame sink error: {:?}", e);
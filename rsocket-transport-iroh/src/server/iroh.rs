@@ -132,7 +132,7 @@ impl ServerTransport for IrohServerTransport {
                         match connection.accept_bi().await {
                             Ok((send_stream, recv_stream)) => {
                                 log::info!("✅ Server: Opened bidirectional stream for incoming connection");
-                                let connection_with_streams = crate::connection::IrohConnectionWithStreams::new(send_stream, recv_stream);
+                                let connection_with_streams = crate::connection::IrohConnectionWithStreams::from_connection(&connection, send_stream, recv_stream);
                                 Some(Ok(IrohClientTransport::from_connection_with_streams(connection_with_streams)))
                             }
                             Err(e) => {
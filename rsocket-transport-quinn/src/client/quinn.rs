@@ -45,7 +45,13 @@ impl Transport for QuinnClientTransport {
                     .await
                     .map_err(|e| RSocketError::Other(e.into()))?;
                 
-                Ok(QuinnConnection::new(send_stream, recv_stream))
+                let local_addr = endpoint.local_addr().ok();
+                Ok(QuinnConnection::from_connection(
+                    &connection,
+                    local_addr,
+                    send_stream,
+                    recv_stream,
+                ))
             }
         }
     }
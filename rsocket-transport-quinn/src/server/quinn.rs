@@ -48,7 +48,12 @@ impl ServerTransport for QuinnServerTransport {
                             Ok(connection) => {
                                 match connection.accept_bi().await {
                                     Ok((send_stream, recv_stream)) => {
-                                        let quinn_connection = QuinnConnection::new(send_stream, recv_stream);
+                                        let quinn_connection = QuinnConnection::from_connection(
+                                            &connection,
+                                            endpoint.local_addr().ok(),
+                                            send_stream,
+                                            recv_stream,
+                                        );
                                         Some(Ok(QuinnClientTransport::from_quinn_connection(quinn_connection)))
                                     }
                                     Err(e) => Some(Err(RSocketError::Other(e.into()).into())),
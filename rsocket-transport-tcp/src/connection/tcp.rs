@@ -1,8 +1,9 @@
 use futures::{SinkExt, StreamExt};
 use rsocket_rust::error::RSocketError;
-use rsocket_rust::transport::{Connection, FrameSink, FrameStream};
+use rsocket_rust::transport::{Connection, ConnectionInfo, FrameSink, FrameStream, TransportKind};
+use rsocket_rust::utils::LengthBasedFrameWriter;
 use tokio::net::TcpStream;
-use tokio_util::codec::Framed;
+use tokio_util::codec::FramedRead;
 
 use super::codec::LengthBasedFrameCodec;
 
@@ -13,12 +14,21 @@ pub struct TcpConnection {
 
 impl Connection for TcpConnection {
     fn split(self) -> (Box<FrameSink>, Box<FrameStream>) {
-        let (sink, stream) = Framed::new(self.stream, LengthBasedFrameCodec).split();
+        let (read, write) = self.stream.into_split();
+        let sink = LengthBasedFrameWriter::new(write);
+        let stream = FramedRead::new(read, LengthBasedFrameCodec);
         (
             Box::new(sink.sink_map_err(|e| RSocketError::Other(e.into()))),
             Box::new(stream.map(|next| next.map_err(|e| RSocketError::Other(e.into())))),
         )
     }
+
+    fn info(&self) -> Option<ConnectionInfo> {
+        let info = ConnectionInfo::new(TransportKind::Tcp)
+            .set_local_addr(self.stream.local_addr().ok())
+            .set_remote_addr(self.stream.peer_addr().ok());
+        Some(info)
+    }
 }
 
 impl From<TcpStream> for TcpConnection {
@@ -1,9 +1,13 @@
+use bytes::Bytes;
 use futures::{SinkExt, StreamExt};
 use rsocket_rust::error::RSocketError;
-use rsocket_rust::transport::{Connection, FrameSink, FrameStream};
+use rsocket_rust::transport::{
+    Connection, ConnectionInfo, FrameSink, FrameStream, PeerIdentity, TransportKind,
+};
+use rsocket_rust::utils::LengthBasedFrameWriter;
 use tokio::net::TcpStream;
 use tokio_native_tls::TlsStream;
-use tokio_util::codec::Framed;
+use tokio_util::codec::FramedRead;
 
 use super::codec::LengthBasedFrameCodec;
 
@@ -14,12 +18,31 @@ pub struct TlsConnection {
 
 impl Connection for TlsConnection {
     fn split(self) -> (Box<FrameSink>, Box<FrameStream>) {
-        let (sink, stream) = Framed::new(self.stream, LengthBasedFrameCodec).split();
+        let (read, write) = tokio::io::split(self.stream);
+        let sink = LengthBasedFrameWriter::new(write);
+        let stream = FramedRead::new(read, LengthBasedFrameCodec);
         (
             Box::new(sink.sink_map_err(|e| RSocketError::Other(e.into()))),
             Box::new(stream.map(|it| it.map_err(|e| RSocketError::Other(e.into())))),
         )
     }
+
+    fn info(&self) -> Option<ConnectionInfo> {
+        let tls = self.stream.get_ref();
+        let socket = tls.get_ref().get_ref();
+        // native-tls only exposes the leaf certificate.
+        let identity = tls
+            .peer_certificate()
+            .ok()
+            .flatten()
+            .and_then(|it| it.to_der().ok())
+            .map(|it| PeerIdentity::Certificates(vec![Bytes::from(it)]));
+        let info = ConnectionInfo::new(TransportKind::Tls)
+            .set_local_addr(socket.local_addr().ok())
+            .set_remote_addr(socket.peer_addr().ok())
+            .set_identity(identity);
+        Some(info)
+    }
 }
 
 impl From<TlsStream<TcpStream>> for TlsConnection {
@@ -1,8 +1,11 @@
 use futures::{SinkExt, StreamExt};
 use rsocket_rust::error::RSocketError;
-use rsocket_rust::transport::{Connection, FrameSink, FrameStream};
+use rsocket_rust::transport::{
+    Connection, ConnectionInfo, FrameSink, FrameStream, PeerIdentity, TransportKind,
+};
+use rsocket_rust::utils::LengthBasedFrameWriter;
 use tokio::net::UnixStream;
-use tokio_util::codec::Framed;
+use tokio_util::codec::FramedRead;
 
 use super::codec::LengthBasedFrameCodec;
 
@@ -13,12 +16,32 @@ pub struct UnixConnection {
 
 impl Connection for UnixConnection {
     fn split(self) -> (Box<FrameSink>, Box<FrameStream>) {
-        let (sink, stream) = Framed::new(self.stream, LengthBasedFrameCodec).split();
+        let (read, write) = self.stream.into_split();
+        let sink = LengthBasedFrameWriter::new(write);
+        let stream = FramedRead::new(read, LengthBasedFrameCodec);
         (
             Box::new(sink.sink_map_err(|e| RSocketError::Other(e.into()))),
             Box::new(stream.map(|it| it.map_err(|e| RSocketError::Other(e.into())))),
         )
     }
+
+    fn info(&self) -> Option<ConnectionInfo> {
+        let identity = self.stream.peer_cred().ok().map(|it| PeerIdentity::Unix {
+            uid: it.uid(),
+            gid: it.gid(),
+            pid: it.pid(),
+        });
+        let mut info = ConnectionInfo::new(TransportKind::Unix).set_identity(identity);
+        let local = self.stream.local_addr().ok();
+        if let Some(path) = local.as_ref().and_then(|it| it.as_pathname()) {
+            info = info.set_attribute("local_path", path.to_string_lossy());
+        }
+        let remote = self.stream.peer_addr().ok();
+        if let Some(path) = remote.as_ref().and_then(|it| it.as_pathname()) {
+            info = info.set_attribute("remote_path", path.to_string_lossy());
+        }
+        Some(info)
+    }
 }
 
 impl From<UnixStream> for UnixConnection {
@@ -2,9 +2,11 @@ use std::net::SocketAddr;
 
 use rsocket_rust::{async_trait, error::RSocketError, transport::Transport, Result};
 use tokio::net::TcpStream;
-use tokio_tungstenite::{
-    accept_async, connect_async, tungstenite::handshake::client::Request, MaybeTlsStream,
+use tokio_tungstenite::tungstenite::handshake::client::Request;
+use tokio_tungstenite::tungstenite::handshake::server::{
+    Callback, ErrorResponse, Request as UpgradeRequest, Response as UpgradeResponse,
 };
+use tokio_tungstenite::{accept_hdr_async, connect_async, MaybeTlsStream};
 use url::Url;
 
 use super::connection::WebsocketConnection;
@@ -21,6 +23,26 @@ pub(crate) enum Connector {
     Request(WebsocketRequest),
 }
 
+/// Keeps the path and headers of the upgrade request for the acceptor.
+struct RecordUpgrade<'a>(&'a mut Vec<(String, String)>);
+
+impl Callback for RecordUpgrade<'_> {
+    fn on_request(
+        self,
+        req: &UpgradeRequest,
+        res: UpgradeResponse,
+    ) -> std::result::Result<UpgradeResponse, ErrorResponse> {
+        self.0
+            .push(("path".to_owned(), req.uri().path().to_owned()));
+        for (k, v) in req.headers() {
+            if let Ok(v) = v.to_str() {
+                self.0.push((format!("header.{}", k), v.to_owned()));
+            }
+        }
+        Ok(res)
+    }
+}
+
 #[derive(Debug)]
 pub struct WebsocketClientTransport {
     connector: Connector,
@@ -38,16 +60,19 @@ impl Transport for WebsocketClientTransport {
 
     async fn connect(self) -> Result<WebsocketConnection> {
         match self.connector {
-            Connector::Direct(stream) => match accept_async(stream).await {
-                Ok(ws) => Ok(WebsocketConnection::new(ws)),
-                Err(e) => Err(RSocketError::Other(e.into()).into()),
-            },
+            Connector::Direct(stream) => {
+                let mut attributes = vec![];
+                match accept_hdr_async(stream, RecordUpgrade(&mut attributes)).await {
+                    Ok(ws) => Ok(WebsocketConnection::new(ws, attributes)),
+                    Err(e) => Err(RSocketError::Other(e.into()).into()),
+                }
+            }
             Connector::Url(u) => match connect_async(u).await {
-                Ok((stream, _)) => Ok(WebsocketConnection::new(stream)),
+                Ok((stream, _)) => Ok(WebsocketConnection::new(stream, vec![])),
                 Err(e) => Err(RSocketError::Other(e.into()).into()),
             },
             Connector::Request(req) => match connect_async(req).await {
-                Ok((stream, _)) => Ok(WebsocketConnection::new(stream)),
+                Ok((stream, _)) => Ok(WebsocketConnection::new(stream, vec![])),
                 Err(e) => Err(RSocketError::Other(e.into()).into()),
             },
         }
@@ -6,7 +6,7 @@ use futures::{Sink, SinkExt, StreamExt};
 use rsocket_rust::{
     error::RSocketError,
     frame::Frame,
-    transport::{Connection, FrameSink, FrameStream},
+    transport::{Connection, ConnectionInfo, FrameSink, FrameStream, TransportKind},
     utils::Writeable,
 };
 use tokio::net::TcpStream;
@@ -19,11 +19,27 @@ use tokio_tungstenite::{
 #[derive(Debug)]
 pub struct WebsocketConnection {
     stream: WebSocketStream<MaybeTlsStream<TcpStream>>,
+    info: ConnectionInfo,
 }
 
 impl WebsocketConnection {
-    pub(crate) fn new(stream: WebSocketStream<MaybeTlsStream<TcpStream>>) -> WebsocketConnection {
-        WebsocketConnection { stream }
+    /// Wraps an upgraded stream, with the attributes of the upgrade request if it was accepted.
+    pub(crate) fn new(
+        stream: WebSocketStream<MaybeTlsStream<TcpStream>>,
+        attributes: Vec<(String, String)>,
+    ) -> WebsocketConnection {
+        let socket = match stream.get_ref() {
+            MaybeTlsStream::Plain(it) => Some(it),
+            MaybeTlsStream::NativeTls(it) => Some(it.get_ref().get_ref().get_ref()),
+            _ => None,
+        };
+        let mut info = ConnectionInfo::new(TransportKind::WebSocket)
+            .set_local_addr(socket.and_then(|it| it.local_addr().ok()))
+            .set_remote_addr(socket.and_then(|it| it.peer_addr().ok()));
+        for (k, v) in attributes {
+            info = info.set_attribute(k, v);
+        }
+        WebsocketConnection { stream, info }
     }
 }
 
@@ -80,4 +96,8 @@ impl Connection for WebsocketConnection {
             })),
         )
     }
+
+    fn info(&self) -> Option<ConnectionInfo> {
+        Some(self.info.clone())
+    }
 }
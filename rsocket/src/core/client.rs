@@ -4,25 +4,40 @@ use std::pin::Pin;
 use std::sync::Arc;
 use std::time::Duration;
 
+use async_stream::stream;
 use async_trait::async_trait;
+use bytes::{Bytes, BytesMut};
 use futures::{future, FutureExt, Sink, SinkExt, Stream, StreamExt};
-use tokio::sync::{mpsc, Mutex, Notify};
+use tokio::io::AsyncRead;
+use tokio::sync::{mpsc, oneshot, watch, Mutex};
 
-use crate::error::{RSocketError, ERR_CONN_CLOSED};
+use crate::error::{RSocketError, ERR_CONN_FAILED};
+use crate::extension::{CompositeMetadata, CompositeMetadataEntry, DeadlineMetadata, MimeType};
 use crate::frame::{self, Frame};
 use crate::payload::{Payload, SetupPayload, SetupPayloadBuilder};
 use crate::runtime;
 use crate::spi::{ClientResponder, Flux, RSocket};
 use crate::transport::{
-    self, Connection, DuplexSocket, FrameSink, FrameStream, ClientRequester, Splitter, Transport,
+    self, ClientRequester, Connection, DispatchOptions, DuplexSocket, FrameSink, FrameStream,
+    Handover, Liveness, QueueDepth, QueueOptions, ReassemblyOptions, ResumeOptions, ResumeState,
+    SharedResumeState, Splitter, StreamInfo, StreamOptions, Subscription, Transport,
 };
+use crate::utils::Writeable;
 use crate::Result;
 
 #[derive(Clone)]
 pub struct Client {
-    closed: Arc<Notify>,
+    closed: watch::Receiver<bool>,
     requester: ClientRequester,
     closing: mpsc::Sender<()>,
+    deadlines: Deadlines,
+}
+
+/// Default deadline of the requests of a client, and whether it is sent to the responder.
+#[derive(Clone, Copy, Default)]
+struct Deadlines {
+    timeout: Option<Duration>,
+    propagate: bool,
 }
 
 pub struct ClientBuilder<T, C> {
@@ -30,6 +45,11 @@ pub struct ClientBuilder<T, C> {
     setup: SetupPayloadBuilder,
     responder: Option<ClientResponder>,
     closer: Option<Box<dyn FnMut() + Send + Sync>>,
+    resume: Option<(ResumeOptions, Box<dyn Fn() -> T + Send + Sync>)>,
+    queue: QueueOptions,
+    dispatch: DispatchOptions,
+    reassembly: ReassemblyOptions,
+    deadlines: Deadlines,
     mtu: usize,
     _c: PhantomData<C>,
 }
@@ -45,6 +65,11 @@ where
             responder: None,
             setup: SetupPayload::builder(),
             closer: None,
+            resume: None,
+            queue: QueueOptions::default(),
+            dispatch: DispatchOptions::default(),
+            reassembly: ReassemblyOptions::default(),
+            deadlines: Deadlines::default(),
             mtu: 0,
             _c: PhantomData,
         }
@@ -103,6 +128,48 @@ where
         self
     }
 
+    /// Honors leases: requests are only sent within the leases granted by the server, and
+    /// fail with `RSocketError::RequestRejected` otherwise.
+    pub fn lease(mut self) -> Self {
+        self.setup = self.setup.set_honor_lease(true);
+        self
+    }
+
+    /// Bounds the queue of outbound frames, requests wait for room once it is full.
+    pub fn outbound_queue(mut self, opts: QueueOptions) -> Self {
+        self.queue = opts;
+        self
+    }
+
+    /// Sets how the fire_and_forget and metadata_push handlers of the acceptor are run.
+    pub fn dispatch_options(mut self, opts: DispatchOptions) -> Self {
+        self.dispatch = opts;
+        self
+    }
+
+    /// Bounds the fragments received from the server while reassembling them.
+    pub fn reassembly_options(mut self, opts: ReassemblyOptions) -> Self {
+        self.reassembly = opts;
+        self
+    }
+
+    /// Sets the default deadline of requests, streams and channels, which get cancelled and
+    /// fail with `RSocketError::Timeout` once it has elapsed.
+    pub fn request_timeout(mut self, timeout: Duration) -> Self {
+        self.deadlines.timeout = Some(timeout);
+        self
+    }
+
+    /// Sends the deadline of each request to the responder, which aborts the request once it
+    /// has elapsed.
+    ///
+    /// The deadline is carried by a `DeadlineMetadata` entry, so the metadata MIME type of the
+    /// connection must be composite metadata.
+    pub fn propagate_deadline(mut self) -> Self {
+        self.deadlines.propagate = true;
+        self
+    }
+
     pub fn acceptor(mut self, acceptor: ClientResponder) -> Self {
         self.responder = Some(acceptor);
         self
@@ -119,6 +186,17 @@ where
     T: Send + Sync + Transport<Conn = C> + 'static,
     C: Send + Sync + Connection + 'static,
 {
+    /// Makes the session resumable: when the connection drops, a new transport is created with
+    /// `reconnect` and the session is resumed on it, replaying the frames which got lost.
+    pub fn resume(
+        mut self,
+        opts: ResumeOptions,
+        reconnect: impl Fn() -> T + Send + Sync + 'static,
+    ) -> Self {
+        self.resume = Some((opts, Box::new(reconnect)));
+        self
+    }
+
     pub async fn start(mut self) -> Result<Client> {
         let tp: T = self.transport.take().expect("missint transport");
 
@@ -128,9 +206,11 @@ where
             Some(Splitter::new(self.mtu))
         };
 
-        let (snd_tx, mut snd_rx) = mpsc::unbounded_channel::<Frame>();
-        let cloned_snd_tx = snd_tx.clone();
+        let (snd_tx, snd_rx) = transport::outbound(self.queue);
+        let error_tx = snd_tx.clone();
         let mut socket = DuplexSocket::new(1, snd_tx, splitter);
+        socket.set_dispatch_options(self.dispatch);
+        socket.set_reassembly_options(self.reassembly);
 
         let requester = socket.client_requester();
 
@@ -140,121 +220,355 @@ where
         }
 
         let conn = tp.connect().await?;
-        let (mut sink, mut stream) = conn.split();
-
+        socket.set_connection_info(conn.info());
+        let (sink, mut stream) = conn.split();
+
+        let resume = self.resume.take();
+        let state: Option<SharedResumeState> = resume.as_ref().map(|(opts, _)| {
+            Arc::new(std::sync::Mutex::new(ResumeState::new(
+                opts.get_retained_bytes(),
+            )))
+        });
+        let token = resume.as_ref().map(|(opts, _)| {
+            opts.get_token()
+                .cloned()
+                .unwrap_or_else(transport::generate_token)
+        });
+        if let Some(token) = &token {
+            self.setup = self.setup.set_resume_token(token.clone());
+        }
         let setup = self.setup.build();
+        let composite = setup.metadata_mime_type()
+            == MimeType::MESSAGE_X_RSOCKET_COMPOSITE_METADATA_V0.as_str();
+        if self.deadlines.propagate && !composite {
+            warn!("deadlines are only propagated along with composite metadata");
+            self.deadlines.propagate = false;
+        }
 
         // begin write loop
         let tick_period = setup.keepalive_interval();
+        let lifetime = setup.keepalive_lifetime();
+        let (handovers, handovers_rx) = mpsc::unbounded_channel::<Handover>();
+        let (shutdown, shutdown_rx) = oneshot::channel::<()>();
+        let (flushed, flushed_rx) = oneshot::channel::<()>();
+        let keepalive = Some((tick_period, socket.rtt()));
+        let cloned_state = state.clone();
         runtime::spawn(async move {
-            loop {
-                // send keepalive if timeout
-                match tokio::time::timeout(tick_period, snd_rx.recv()).await {
-                    Ok(Some(frame)) => {
-                        if let frame::Body::Error(e) = frame.get_body_ref() {
-                            if e.get_code() == ERR_CONN_CLOSED {
-                                break;
-                            }
-                        }
-                        if let Err(e) = sink.send(frame).await {
-                            error!("write frame failed: {}", e);
-                            break;
-                        }
-                    }
-                    Ok(None) => break,
-                    Err(_) => {
-                        // keepalive
-                        let keepalive_frame =
-                            frame::Keepalive::builder(0, Frame::FLAG_RESPOND).build();
-                        if let Err(e) = sink.send(keepalive_frame).await {
-                            error!("write frame failed: {}", e);
-                            break;
-                        }
-                    }
-                }
-            }
+            transport::write_loop(
+                snd_rx,
+                handovers_rx,
+                shutdown_rx,
+                Some(sink),
+                cloned_state,
+                keepalive,
+            )
+            .await;
+            let _ = flushed.send(());
         });
 
         // begin read loop
         let closer = self.closer.take();
-        let close_notify = Arc::new(Notify::new());
-        let close_notify_clone = close_notify.clone();
+        let (closed_tx, closed) = watch::channel(false);
         let (closing, mut closing_rx) = mpsc::channel::<()>(1);
 
-        let (read_tx, mut read_rx) = mpsc::unbounded_channel::<Frame>();
+        let (read_tx, mut read_rx) = mpsc::channel::<Frame>(transport::READ_AHEAD);
 
         // read frames from stream, then writes into channel
         runtime::spawn(async move {
+            let liveness = Liveness::new();
             loop {
                 tokio::select! {
-                    res = stream.next() => {
-                        match res {
-                            Some(next) => match next {
-                                Ok(frame) => {
-                                    if let Err(e) = read_tx.send(frame) {
-                                        error!("forward frame failed: {}", e);
-                                        break;
-                                    }
-                                }
-                                Err(e) => {
-                                    error!("read frame failed: {}", e);
-                                    break;
-                                }
+                    _ = transport::read_loop(stream, &read_tx, state.as_ref(), &liveness) => {}
+                    _ = liveness.expired(lifetime) => {
+                        warn!("no frame received for {:?}, the connection is dead", lifetime);
+                        if resume.is_none() {
+                            let sending = frame::Error::builder(0, 0)
+                                .set_code(ERR_CONN_FAILED)
+                                .set_data(Bytes::from("keepalive timeout"))
+                                .build();
+                            if let Err(e) = error_tx.send(sending) {
+                                debug!("send keepalive timeout failed: {}", e);
                             }
-                            None => break,
                         }
                     }
-                    _ = closing_rx.recv() => {
-                        break
-                    }
+                    _ = closing_rx.recv() => break,
+                    _ = read_tx.closed() => break,
                 }
+                let (opts, reconnect) = match &resume {
+                    Some(it) => it,
+                    None => break,
+                };
+                let (token, state) = (token.as_ref().unwrap(), state.as_ref().unwrap());
+                let resumed = tokio::select! {
+                    it = Self::reconnect(opts, reconnect, token, state, &handovers) => it,
+                    _ = closing_rx.recv() => None,
+                };
+                stream = match resumed {
+                    Some(it) => it,
+                    None => break,
+                };
+                liveness.touch();
             }
         });
 
-        
         socket.setup(setup).await?;
 
         // process frames
         runtime::spawn(async move {
             while let Some(next) = read_rx.recv().await {
                 if let Err(e) = socket.dispatch(next, None).await {
-                    error!("dispatch frame failed: {}", e);
+                    info!("connection terminated: {}", e);
                     break;
                 }
             }
+            socket.fail_pending();
 
-            // workaround: send a notify frame that the connection has been closed.
-            let close_frame = frame::Error::builder(0, 0)
-                .set_code(ERR_CONN_CLOSED)
-                .build();
-            if let Err(e) = cloned_snd_tx.send(close_frame) {
-                debug!("send close notify frame failed: {}", e);
-            }
-
-            // notify client closed
-            close_notify_clone.notify_one();
+            // stop the writer once the pending frames are flushed.
+            let _ = shutdown.send(());
+            let _ = flushed_rx.await;
 
             // invoke on_close handler
             if let Some(mut invoke) = closer {
                 invoke();
             }
+
+            // notify client closed
+            let _ = closed_tx.send(true);
         });
 
-        Ok(Client::new(requester, close_notify, closing))
+        Ok(Client::new(requester, closed, closing, self.deadlines))
+    }
+
+    /// Resumes a broken session on a new connection, returning its inbound frames.
+    async fn reconnect(
+        opts: &ResumeOptions,
+        reconnect: &(dyn Fn() -> T + Send + Sync),
+        token: &Bytes,
+        state: &SharedResumeState,
+        handovers: &mpsc::UnboundedSender<Handover>,
+    ) -> Option<Box<FrameStream>> {
+        let deadline = tokio::time::Instant::now() + opts.get_session_duration();
+        loop {
+            match Self::try_resume(reconnect(), token, state).await {
+                Ok((sink, stream, position)) => {
+                    info!("session resumed at position {}", position);
+                    handovers.send((sink, position)).ok()?;
+                    return Some(stream);
+                }
+                Err(e) => {
+                    if let Some(RSocketError::RejectedResume(_)) = e.downcast_ref() {
+                        error!("resume session failed: {}", e);
+                        return None;
+                    }
+                    warn!("resume session failed, retry later: {}", e);
+                }
+            }
+            let next = tokio::time::Instant::now() + opts.get_reconnect_interval();
+            if next >= deadline {
+                error!("resume session failed: timeout");
+                return None;
+            }
+            tokio::time::sleep_until(next).await;
+        }
+    }
+
+    async fn try_resume(
+        tp: T,
+        token: &Bytes,
+        state: &SharedResumeState,
+    ) -> Result<(Box<FrameSink>, Box<FrameStream>, u64)> {
+        let conn = tp.connect().await?;
+        let (mut sink, mut stream) = conn.split();
+        let position = transport::resume_session(&mut sink, &mut stream, token, state).await?;
+        Ok((sink, stream, position))
     }
 }
 
 impl Client {
-    fn new(requester: ClientRequester, closed: Arc<Notify>, closing: mpsc::Sender<()>) -> Client {
+    fn new(
+        requester: ClientRequester,
+        closed: watch::Receiver<bool>,
+        closing: mpsc::Sender<()>,
+        deadlines: Deadlines,
+    ) -> Client {
         Client {
             requester,
             closed,
             closing,
+            deadlines,
         }
     }
 
     pub async fn wait_for_close(self) {
-        self.closed.notified().await
+        let mut closed = self.closed;
+        while !*closed.borrow() {
+            if closed.changed().await.is_err() {
+                break;
+            }
+        }
+    }
+
+    /// Closes the connection, cancelling the interactions in flight.
+    pub async fn close(&self) {
+        self.dispose_gracefully(Duration::from_secs(0)).await
+    }
+
+    /// Closes the connection once the interactions in flight have completed, or cancels them
+    /// after `timeout`. New requests fail with `RSocketError::ConnectionClosed` right away.
+    ///
+    /// Returns when the pending frames have been flushed and the connection is shut down.
+    pub async fn dispose_gracefully(&self, timeout: Duration) {
+        self.requester.dispose(timeout).await;
+        // the reader may be gone already if the connection was lost.
+        let _ = self.closing.try_send(());
+        self.clone().wait_for_close().await
+    }
+
+    /// Latest round-trip time, measured with KEEPALIVE frames.
+    pub fn rtt(&self) -> Option<Duration> {
+        self.requester.rtt().latest()
+    }
+
+    /// Exponentially weighted moving average of the round-trip times.
+    pub fn rtt_ewma(&self) -> Option<Duration> {
+        self.requester.rtt().ewma()
+    }
+
+    /// Stream of round-trip time samples, skipping those which arrive while the previous one
+    /// is not consumed yet.
+    pub fn rtt_samples(&self) -> Flux<Duration> {
+        let mut samples = self.requester.rtt().watch();
+        Box::pin(stream! {
+            while samples.changed().await.is_ok() {
+                let sample = *samples.borrow();
+                if let Some(it) = sample {
+                    yield it;
+                }
+            }
+        })
+    }
+
+    /// Frames waiting to be written to the connection.
+    pub fn queue_depth(&self) -> QueueDepth {
+        self.requester.queue_depth()
+    }
+
+    /// Streams in flight on the connection, ordered by stream id.
+    pub fn active_streams(&self) -> Vec<StreamInfo> {
+        self.requester.active_streams()
+    }
+
+    /// Request-Response interaction failing with `RSocketError::Timeout` once `timeout` has
+    /// elapsed, instead of the default deadline of the client.
+    pub async fn request_response_with(
+        &self,
+        req: Payload,
+        timeout: Duration,
+    ) -> Result<Option<Payload>> {
+        let req = self.deadlines.attach(req, Some(timeout));
+        self.requester
+            .request_response_with(req, Some(timeout))
+            .await
+    }
+
+    /// Request-Response interaction whose data is read from `body`.
+    ///
+    /// The data is cut into fragments as it is read instead of being held in memory, which
+    /// suits large uploads. Fragments are as large as the mtu set with `fragment` allows.
+    ///
+    /// # Example
+    /// ```no_run,ignore
+    /// let file = tokio::fs::File::open("artifact.tar.gz").await?;
+    /// let res = cli.request_response_from(Some(Bytes::from("artifact.tar.gz")), file).await?;
+    /// ```
+    pub async fn request_response_from<R>(
+        &self,
+        metadata: Option<Bytes>,
+        body: R,
+    ) -> Result<Option<Payload>>
+    where
+        R: AsyncRead + Unpin + Send + 'static,
+    {
+        let timeout = self.deadlines.timeout;
+        let (_, metadata) = self
+            .deadlines
+            .attach(Payload::new(None, metadata), timeout)
+            .split();
+        self.requester
+            .request_response_from(metadata, body, timeout)
+            .await
+    }
+
+    /// Fire-and-Forget interaction whose data is read from `body`, cut into fragments as it is
+    /// read.
+    pub async fn fire_and_forget_from<R>(&self, metadata: Option<Bytes>, body: R) -> Result<()>
+    where
+        R: AsyncRead + Unpin,
+    {
+        self.requester.fire_and_forget_from(metadata, body).await
+    }
+
+    /// Request-Stream interaction with explicit control over the demand sent to the responder.
+    pub fn request_stream_with(&self, req: Payload, opts: StreamOptions) -> Subscription {
+        let opts = self.deadlines.apply(opts);
+        let req = self.deadlines.attach(req, opts.get_timeout());
+        self.requester.request_stream_with(req, opts)
+    }
+
+    /// Request-Channel interaction with explicit control over the demand sent to the responder.
+    ///
+    /// A propagated deadline is carried by the first payload of the channel.
+    pub fn request_channel_with(
+        &self,
+        reqs: Flux<Result<Payload>>,
+        opts: StreamOptions,
+    ) -> Subscription {
+        let opts = self.deadlines.apply(opts);
+        let deadlines = self.deadlines;
+        let mut first = true;
+        let reqs = reqs.map(move |it| match it {
+            Ok(req) if first => {
+                first = false;
+                Ok(deadlines.attach(req, opts.get_timeout()))
+            }
+            other => other,
+        });
+        self.requester.request_channel_with(Box::pin(reqs), opts)
+    }
+}
+
+impl Deadlines {
+    /// Sets the default deadline on options without one.
+    fn apply(&self, opts: StreamOptions) -> StreamOptions {
+        match (opts.get_timeout(), self.timeout) {
+            (None, Some(timeout)) => opts.timeout(timeout),
+            _ => opts,
+        }
+    }
+
+    /// Adds the deadline to the composite metadata of a request when it gets propagated.
+    fn attach(&self, req: Payload, timeout: Option<Duration>) -> Payload {
+        let timeout = match timeout {
+            Some(it) if self.propagate => it,
+            _ => return req,
+        };
+        let (data, metadata) = req.split();
+        let mut composite = match &metadata {
+            Some(b) => match CompositeMetadata::decode(&mut BytesMut::from(&b[..])) {
+                Ok(it) => it,
+                Err(e) => {
+                    warn!("deadline not propagated, invalid composite metadata: {}", e);
+                    return Payload::new(data, metadata);
+                }
+            },
+            None => CompositeMetadata::default(),
+        };
+        let deadline = DeadlineMetadata::new(timeout);
+        composite.push(CompositeMetadataEntry::new(
+            DeadlineMetadata::mime_type(),
+            Bytes::from(deadline.bytes()),
+        ));
+        Payload::new(data, Some(composite.into()))
     }
 }
 
@@ -269,14 +583,16 @@ impl RSocket for Client {
     }
 
     async fn request_response(&self, req: Payload) -> Result<Option<Payload>> {
-        self.requester.request_response(req).await
+        let timeout = self.deadlines.timeout;
+        let req = self.deadlines.attach(req, timeout);
+        self.requester.request_response_with(req, timeout).await
     }
 
     fn request_stream(&self, req: Payload) -> Flux<Result<Payload>> {
-        self.requester.request_stream(req)
+        Box::pin(self.request_stream_with(req, StreamOptions::default()))
     }
 
     fn request_channel(&self, reqs: Flux<Result<Payload>>) -> Flux<Result<Payload>> {
-        self.requester.request_channel(reqs)
+        Box::pin(self.request_channel_with(reqs, StreamOptions::default()))
     }
 }
@@ -1,9 +1,13 @@
 mod client;
 mod factory;
-mod server;
 mod multi_transport_server;
+mod registry;
+mod server;
+mod shutdown;
 
 pub use client::{Client, ClientBuilder};
 pub use factory::RSocketFactory;
-pub use server::ServerBuilder;
 pub use multi_transport_server::MultiTransportServerBuilder;
+pub use registry::{ConnectionHandle, ConnectionRegistry};
+pub use server::ServerBuilder;
+pub use shutdown::ShutdownHandle;
@@ -1,23 +1,38 @@
 use std::sync::Arc;
 use std::pin::Pin;
+use std::time::Duration;
 use futures::future::{select_all, BoxFuture};
 use tokio::task::JoinHandle;
 
 use crate::error::RSocketError;
-use crate::spi::ServerResponder;
-use crate::transport::{ServerTransport, Transport};
+use crate::core::server::{ServerOptions, DEFAULT_SETUP_TIMEOUT};
+use crate::core::registry::ConnectionRegistry;
+use crate::core::shutdown::ShutdownHandle;
+use crate::spi::{Acceptor, AsyncServerResponder, LeaseStrategy, ServerResponder};
+use crate::transport::{
+    DispatchOptions, QueueOptions, ReassemblyOptions, ResumeOptions, ServerTransport, SessionStore,
+    Transport,
+};
 use crate::Result;
 
 pub struct MultiTransportServerBuilder {
     transports: Vec<Box<dyn MultiTransportItem>>,
-    acceptor: Option<ServerResponder>,
+    acceptor: Option<Acceptor>,
+    setup_timeout: Duration,
+    registry: Option<ConnectionRegistry>,
     start_handler: Option<Box<dyn FnMut() + Send + Sync>>,
+    resume: Option<ResumeOptions>,
+    lease_strategy: Option<Arc<dyn LeaseStrategy>>,
+    queue: QueueOptions,
+    dispatch: DispatchOptions,
+    reassembly: ReassemblyOptions,
+    shutdown: ShutdownHandle,
     mtu: usize,
 }
 
 trait MultiTransportItem: Send + Sync {
     fn start(&mut self) -> BoxFuture<'_, Result<()>>;
-    fn spawn_listener(&mut self, acceptor: Arc<Option<ServerResponder>>, mtu: usize) -> JoinHandle<Result<()>>;
+    fn spawn_listener(&mut self, opts: Arc<ServerOptions>) -> JoinHandle<Result<()>>;
     fn name(&self) -> &str;
 }
 
@@ -60,21 +75,33 @@ where
         })
     }
 
-    fn spawn_listener(&mut self, acceptor: Arc<Option<ServerResponder>>, mtu: usize) -> JoinHandle<Result<()>> {
+    fn spawn_listener(&mut self, opts: Arc<ServerOptions>) -> JoinHandle<Result<()>> {
         let mut transport = self.transport.take().expect("Transport not available");
         let name = self.name.clone();
         
         tokio::spawn(async move {
             log::info!("Starting {} transport listener", name);
-            
-            while let Some(next) = transport.next().await {
+            let mut signal = opts.shutdown.clone();
+
+            loop {
+                let next = tokio::select! {
+                    next = transport.next() => next,
+                    _ = signal.wait() => break,
+                };
+                let next = match next {
+                    Some(it) => it,
+                    None => break,
+                };
                 match next {
                     Ok(tp) => {
-                        let acceptor = acceptor.clone();
+                        let opts = opts.clone();
                         let transport_name = name.clone();
                         crate::runtime::spawn(async move {
                             log::debug!("New connection on {} transport", transport_name);
-                            if let Err(e) = crate::core::server::ServerBuilder::<T, C>::on_transport(mtu, tp, acceptor).await {
+                            if let Err(e) =
+                                crate::core::server::ServerBuilder::<T, C>::on_transport(tp, opts)
+                                    .await
+                            {
                                 log::error!("Handle {} transport failed: {}", transport_name, e);
                             }
                         });
@@ -100,7 +127,15 @@ impl MultiTransportServerBuilder {
         Self {
             transports: Vec::new(),
             acceptor: None,
+            setup_timeout: DEFAULT_SETUP_TIMEOUT,
+            registry: None,
             start_handler: None,
+            resume: None,
+            lease_strategy: None,
+            queue: QueueOptions::default(),
+            dispatch: DispatchOptions::default(),
+            reassembly: ReassemblyOptions::default(),
+            shutdown: ShutdownHandle::new(),
             mtu: 0,
         }
     }
@@ -116,7 +151,25 @@ impl MultiTransportServerBuilder {
     }
 
     pub fn acceptor(mut self, handler: ServerResponder) -> Self {
-        self.acceptor = Some(handler);
+        self.acceptor = Some(Acceptor::Sync(handler));
+        self
+    }
+
+    /// Accepts connections with an acceptor which may wait for I/O.
+    pub fn acceptor_async(mut self, handler: AsyncServerResponder) -> Self {
+        self.acceptor = Some(Acceptor::Async(handler));
+        self
+    }
+
+    /// Rejects the SETUP when an async acceptor takes longer than this, 30 seconds by default.
+    pub fn setup_timeout(mut self, timeout: Duration) -> Self {
+        self.setup_timeout = timeout;
+        self
+    }
+
+    /// Tracks the live connections of every transport in the given registry.
+    pub fn registry(mut self, registry: ConnectionRegistry) -> Self {
+        self.registry = Some(registry);
         self
     }
 
@@ -133,6 +186,42 @@ impl MultiTransportServerBuilder {
         self
     }
 
+    /// Accepts resumable sessions on every transport, a session may resume on any of them.
+    pub fn resume(mut self, opts: ResumeOptions) -> Self {
+        self.resume = Some(opts);
+        self
+    }
+
+    /// Grants leases with the given strategy to the clients which honor them.
+    pub fn lease(mut self, strategy: impl LeaseStrategy + 'static) -> Self {
+        self.lease_strategy = Some(Arc::new(strategy));
+        self
+    }
+
+    /// Bounds the queue of outbound frames of each connection.
+    pub fn outbound_queue(mut self, opts: QueueOptions) -> Self {
+        self.queue = opts;
+        self
+    }
+
+    /// Sets how the fire_and_forget and metadata_push handlers of each connection are run.
+    pub fn dispatch_options(mut self, opts: DispatchOptions) -> Self {
+        self.dispatch = opts;
+        self
+    }
+
+    /// Bounds the fragments received from each client while reassembling them.
+    pub fn reassembly_options(mut self, opts: ReassemblyOptions) -> Self {
+        self.reassembly = opts;
+        self
+    }
+
+    /// Lets `serve` be stopped gracefully with the given handle, which stops every transport.
+    pub fn shutdown_handle(mut self, handle: ShutdownHandle) -> Self {
+        self.shutdown = handle;
+        self
+    }
+
     pub async fn serve(mut self) -> Result<()> {
         if self.transports.is_empty() {
             return Err(RSocketError::Other(anyhow::anyhow!("No transports configured")).into());
@@ -147,19 +236,39 @@ impl MultiTransportServerBuilder {
             invoke();
         }
 
-        let acceptor = Arc::new(self.acceptor);
-        let mtu = self.mtu;
+        let (signal, mut drained) = self.shutdown.signal();
+        let opts = Arc::new(ServerOptions {
+            mtu: self.mtu,
+            acceptor: self.acceptor,
+            setup_timeout: self.setup_timeout,
+            registry: self.registry,
+            sessions: self.resume.map(|opts| Arc::new(SessionStore::new(opts))),
+            lease_strategy: self.lease_strategy,
+            queue: self.queue,
+            dispatch: self.dispatch,
+            reassembly: self.reassembly,
+            shutdown: signal,
+        });
 
         let mut handles = Vec::new();
         for mut transport in self.transports {
-            let handle = transport.spawn_listener(acceptor.clone(), mtu);
+            let handle = transport.spawn_listener(opts.clone());
             handles.push(handle);
         }
 
         log::info!("Multi-transport server started with {} transports", handles.len());
 
-        let (result, _index, _remaining) = select_all(handles).await;
-        
+        drop(opts);
+        let (result, _index, remaining) = select_all(handles).await;
+
+        if self.shutdown.is_shutdown() {
+            // wait for the other listeners, then for the live connections to be closed.
+            for handle in remaining {
+                let _ = handle.await;
+            }
+            let _ = drained.recv().await;
+        }
+
         match result {
             Ok(transport_result) => transport_result,
             Err(e) => Err(RSocketError::Other(anyhow::anyhow!("Transport task failed: {}", e)).into()),
@@ -0,0 +1,259 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use dashmap::DashMap;
+use futures::future;
+use tokio::sync::Notify;
+
+use crate::payload::{Payload, SetupPayload};
+use crate::spi::RSocket;
+use crate::transport::ConnectionInfo;
+use crate::Result;
+
+/// Live connections of a server, for it to address and broadcast to its clients.
+///
+/// A connection is registered once its SETUP is received, before the acceptor runs, and stays
+/// registered until it is closed. Its id is the one of `SetupPayload::connection_id` and
+/// `RequestContext::connection_id`.
+///
+/// # Example
+/// ```no_run,ignore
+/// let registry = ConnectionRegistry::new();
+/// let tagging = registry.clone();
+/// RSocketFactory::receive()
+///     .transport(TcpServerTransport::from("127.0.0.1:7878"))
+///     .registry(registry.clone())
+///     .acceptor(Box::new(move |setup, _socket| {
+///         if let (Some(id), Some(user)) = (setup.connection_id(), setup.data()) {
+///             tagging.tag(id, format!("user:{}", String::from_utf8_lossy(user)));
+///         }
+///         Ok(Box::new(EchoRSocket))
+///     }))
+///     .serve()
+///     .await?;
+///
+/// // later on, notify every connection of a user.
+/// let notice = Payload::from("you've got mail");
+/// registry.broadcast_fire_and_forget(notice, |it| it.has_tag("user:alice")).await;
+/// ```
+#[derive(Clone, Default)]
+pub struct ConnectionRegistry {
+    connections: Arc<DashMap<u64, ConnectionHandle>>,
+}
+
+/// A connection tracked by a `ConnectionRegistry`.
+#[derive(Clone)]
+pub struct ConnectionHandle {
+    inner: Arc<Registered>,
+}
+
+struct Registered {
+    id: u64,
+    setup: SetupPayload,
+    requester: Box<dyn RSocket>,
+    tags: Mutex<HashSet<String>>,
+    disconnect: Arc<Notify>,
+}
+
+impl ConnectionRegistry {
+    pub fn new() -> ConnectionRegistry {
+        ConnectionRegistry::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.connections.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.connections.is_empty()
+    }
+
+    pub fn get(&self, id: u64) -> Option<ConnectionHandle> {
+        self.connections.get(&id).map(|it| it.clone())
+    }
+
+    /// Every live connection, sorted by id.
+    pub fn connections(&self) -> Vec<ConnectionHandle> {
+        let mut connections: Vec<ConnectionHandle> =
+            self.connections.iter().map(|it| it.clone()).collect();
+        connections.sort_by_key(|it| it.id());
+        connections
+    }
+
+    /// Live connections carrying the given tag, sorted by id.
+    pub fn find_by_tag(&self, tag: &str) -> Vec<ConnectionHandle> {
+        let mut connections = self.connections();
+        connections.retain(|it| it.has_tag(tag));
+        connections
+    }
+
+    /// Tags a connection, returns false if it is not registered.
+    pub fn tag(&self, id: u64, tag: impl Into<String>) -> bool {
+        match self.get(id) {
+            Some(it) => {
+                it.tag(tag);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Removes a tag from a connection, returns false if it did not carry it.
+    pub fn untag(&self, id: u64, tag: &str) -> bool {
+        self.get(id).is_some_and(|it| it.untag(tag))
+    }
+
+    /// Closes a connection right away, failing its interactions in flight, after sending
+    /// ERROR[CONNECTION_ERROR] to the client. Returns false if it is not registered.
+    pub fn disconnect(&self, id: u64) -> bool {
+        match self.get(id) {
+            Some(it) => {
+                it.disconnect();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Sends a fire_and_forget to the connections matching `filter`, returns how many it was
+    /// sent to.
+    pub async fn broadcast_fire_and_forget<F>(&self, req: Payload, filter: F) -> usize
+    where
+        F: Fn(&ConnectionHandle) -> bool,
+    {
+        self.broadcast("fire_and_forget", filter, |it| {
+            let req = req.clone();
+            async move { it.requester().fire_and_forget(req).await }
+        })
+        .await
+    }
+
+    /// Sends a metadata_push to the connections matching `filter`, returns how many it was
+    /// sent to.
+    pub async fn broadcast_metadata_push<F>(&self, req: Payload, filter: F) -> usize
+    where
+        F: Fn(&ConnectionHandle) -> bool,
+    {
+        self.broadcast("metadata_push", filter, |it| {
+            let req = req.clone();
+            async move { it.requester().metadata_push(req).await }
+        })
+        .await
+    }
+
+    /// Sends to the connections matching `filter` at once, returns how many it succeeded for.
+    async fn broadcast<F, S, R>(&self, interaction: &str, filter: F, send: S) -> usize
+    where
+        F: Fn(&ConnectionHandle) -> bool,
+        S: Fn(ConnectionHandle) -> R,
+        R: Future<Output = Result<()>>,
+    {
+        let sending = self
+            .connections()
+            .into_iter()
+            .filter(|it| filter(it))
+            .map(|it| {
+                let id = it.id();
+                let sent = send(it);
+                async move {
+                    let sent = sent.await;
+                    if let Err(e) = &sent {
+                        debug!("broadcast {} to {} failed: {}", interaction, id, e);
+                    }
+                    sent.is_ok()
+                }
+            });
+        future::join_all(sending)
+            .await
+            .into_iter()
+            .filter(|it| *it)
+            .count()
+    }
+
+    pub(crate) fn register(
+        &self,
+        id: u64,
+        setup: SetupPayload,
+        requester: Box<dyn RSocket>,
+        disconnect: Arc<Notify>,
+    ) {
+        let registered = Registered {
+            id,
+            setup,
+            requester,
+            tags: Mutex::new(HashSet::new()),
+            disconnect,
+        };
+        let handle = ConnectionHandle {
+            inner: Arc::new(registered),
+        };
+        self.connections.insert(id, handle);
+    }
+
+    pub(crate) fn remove(&self, id: u64) {
+        self.connections.remove(&id);
+    }
+}
+
+impl fmt::Debug for ConnectionRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConnectionRegistry")
+            .field("connections", &self.connections.len())
+            .finish()
+    }
+}
+
+impl ConnectionHandle {
+    pub fn id(&self) -> u64 {
+        self.inner.id
+    }
+
+    /// SETUP the client opened the connection with.
+    pub fn setup(&self) -> &SetupPayload {
+        &self.inner.setup
+    }
+
+    pub fn connection_info(&self) -> Option<&ConnectionInfo> {
+        self.inner.setup.connection_info()
+    }
+
+    /// Requester sending requests to the client.
+    pub fn requester(&self) -> &dyn RSocket {
+        self.inner.requester.as_ref()
+    }
+
+    pub fn tag(&self, tag: impl Into<String>) {
+        self.inner.tags.lock().unwrap().insert(tag.into());
+    }
+
+    pub fn untag(&self, tag: &str) -> bool {
+        self.inner.tags.lock().unwrap().remove(tag)
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.inner.tags.lock().unwrap().contains(tag)
+    }
+
+    pub fn tags(&self) -> Vec<String> {
+        let mut tags: Vec<String> = self.inner.tags.lock().unwrap().iter().cloned().collect();
+        tags.sort();
+        tags
+    }
+
+    /// Closes the connection right away, failing its interactions in flight, after sending
+    /// ERROR[CONNECTION_ERROR] to the client.
+    pub fn disconnect(&self) {
+        self.inner.disconnect.notify_one();
+    }
+}
+
+impl fmt::Debug for ConnectionHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConnectionHandle")
+            .field("id", &self.inner.id)
+            .field("tags", &self.tags())
+            .finish()
+    }
+}
@@ -4,23 +4,58 @@ use std::marker::PhantomData;
 use std::net::SocketAddr;
 use std::pin::Pin;
 use std::sync::Arc;
+use std::time::Duration;
 
+use bytes::Bytes;
 use futures::{SinkExt, StreamExt};
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, oneshot, Notify};
 
-use crate::error::RSocketError;
-use crate::frame::{self, Frame};
+use super::registry::ConnectionRegistry;
+use super::shutdown::{ShutdownHandle, ShutdownSignal};
+use crate::error::{self, RSocketError};
+use crate::frame::{self, Body, Frame};
 use crate::payload::SetupPayload;
 use crate::runtime;
-use crate::spi::{RSocket, ServerResponder};
-use crate::transport::{Connection, DuplexSocket, ServerTransport, Splitter, Transport, MIN_MTU};
+use crate::spi::{Acceptor, AsyncServerResponder, LeaseStrategy, RSocket, ServerResponder};
+use crate::transport::{
+    self, Connection, DispatchOptions, DuplexSocket, Liveness, QueueOptions, ReassemblyOptions,
+    ResumeOptions, ServerTransport, SessionStore, Splitter, Transport, MIN_MTU,
+};
 use crate::utils::EmptyRSocket;
 use crate::Result;
 
+/// Keepalive lifetime of connections which do not start with a SETUP frame.
+const DEFAULT_LIFETIME: Duration = Duration::from_secs(90);
+
+/// How long an async acceptor may take by default before the SETUP gets rejected.
+pub(crate) const DEFAULT_SETUP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Settings shared by the connections accepted by a server.
+pub(crate) struct ServerOptions {
+    pub(crate) mtu: usize,
+    pub(crate) acceptor: Option<Acceptor>,
+    pub(crate) setup_timeout: Duration,
+    pub(crate) registry: Option<ConnectionRegistry>,
+    pub(crate) sessions: Option<Arc<SessionStore>>,
+    pub(crate) lease_strategy: Option<Arc<dyn LeaseStrategy>>,
+    pub(crate) queue: QueueOptions,
+    pub(crate) dispatch: DispatchOptions,
+    pub(crate) reassembly: ReassemblyOptions,
+    pub(crate) shutdown: ShutdownSignal,
+}
+
 pub struct ServerBuilder<T, C> {
     transport: Option<T>,
-    on_setup: Option<ServerResponder>,
+    on_setup: Option<Acceptor>,
+    setup_timeout: Duration,
+    registry: Option<ConnectionRegistry>,
     start_handler: Option<Box<dyn FnMut() + Send + Sync>>,
+    resume: Option<ResumeOptions>,
+    lease_strategy: Option<Arc<dyn LeaseStrategy>>,
+    queue: QueueOptions,
+    dispatch: DispatchOptions,
+    reassembly: ReassemblyOptions,
+    shutdown: ShutdownHandle,
     mtu: usize,
     _c: PhantomData<C>,
 }
@@ -34,7 +69,15 @@ where
         ServerBuilder {
             transport: None,
             on_setup: None,
+            setup_timeout: DEFAULT_SETUP_TIMEOUT,
+            registry: None,
             start_handler: None,
+            resume: None,
+            lease_strategy: None,
+            queue: QueueOptions::default(),
+            dispatch: DispatchOptions::default(),
+            reassembly: ReassemblyOptions::default(),
+            shutdown: ShutdownHandle::new(),
             mtu: 0,
             _c: PhantomData,
         }
@@ -48,7 +91,41 @@ where
     }
 
     pub fn acceptor(mut self, handler: ServerResponder) -> Self {
-        self.on_setup = Some(handler);
+        self.on_setup = Some(Acceptor::Sync(handler));
+        self
+    }
+
+    /// Accepts connections with an acceptor which may wait for I/O. Frames of the connection
+    /// are held back until it resolves.
+    ///
+    /// # Example
+    /// ```no_run,ignore
+    /// RSocketFactory::receive()
+    ///     .transport(TcpServerTransport::from("127.0.0.1:7878"))
+    ///     .acceptor_async(Box::new(|setup, _socket| {
+    ///         Box::pin(async move {
+    ///             // validate the token of the setup against some service...
+    ///             Ok(Box::new(EchoRSocket) as Box<dyn RSocket>)
+    ///         })
+    ///     }))
+    ///     .serve()
+    ///     .await
+    /// ```
+    pub fn acceptor_async(mut self, handler: AsyncServerResponder) -> Self {
+        self.on_setup = Some(Acceptor::Async(handler));
+        self
+    }
+
+    /// Rejects the SETUP with REJECTED_SETUP when an async acceptor takes longer than this,
+    /// 30 seconds by default.
+    pub fn setup_timeout(mut self, timeout: Duration) -> Self {
+        self.setup_timeout = timeout;
+        self
+    }
+
+    /// Tracks the live connections in the given registry.
+    pub fn registry(mut self, registry: ConnectionRegistry) -> Self {
+        self.registry = Some(registry);
         self
     }
 
@@ -61,6 +138,44 @@ where
         self.transport = Some(transport);
         self
     }
+
+    /// Accepts resumable sessions, keeping them alive for a while once their connection drops.
+    pub fn resume(mut self, opts: ResumeOptions) -> Self {
+        self.resume = Some(opts);
+        self
+    }
+
+    /// Grants leases with the given strategy to the clients which honor them, and rejects
+    /// the requests they send without a valid lease.
+    pub fn lease(mut self, strategy: impl LeaseStrategy + 'static) -> Self {
+        self.lease_strategy = Some(Arc::new(strategy));
+        self
+    }
+
+    /// Bounds the queue of outbound frames of each connection, responder streams are polled
+    /// only while it has room.
+    pub fn outbound_queue(mut self, opts: QueueOptions) -> Self {
+        self.queue = opts;
+        self
+    }
+
+    /// Sets how the fire_and_forget and metadata_push handlers of each connection are run.
+    pub fn dispatch_options(mut self, opts: DispatchOptions) -> Self {
+        self.dispatch = opts;
+        self
+    }
+
+    /// Bounds the fragments received from each client while reassembling them.
+    pub fn reassembly_options(mut self, opts: ReassemblyOptions) -> Self {
+        self.reassembly = opts;
+        self
+    }
+
+    /// Lets `serve` be stopped gracefully with the given handle.
+    pub fn shutdown_handle(mut self, handle: ShutdownHandle) -> Self {
+        self.shutdown = handle;
+        self
+    }
 }
 
 impl<T, C> ServerBuilder<T, C>
@@ -72,21 +187,39 @@ where
         let mut server_transport = self.transport.take().expect("missing transport");
         // let acceptor = self.on_setup.map(|v| Acceptor::Generate(Arc::new(v)));
 
-        let mtu = self.mtu;
-
         server_transport.start().await?;
 
         if let Some(mut invoke) = self.start_handler {
             invoke();
         }
 
-        let acceptor = Arc::new(self.on_setup);
-        while let Some(next) = server_transport.next().await {
+        let (mut signal, mut drained) = self.shutdown.signal();
+        let opts = Arc::new(ServerOptions {
+            mtu: self.mtu,
+            acceptor: self.on_setup,
+            setup_timeout: self.setup_timeout,
+            registry: self.registry,
+            sessions: self.resume.map(|opts| Arc::new(SessionStore::new(opts))),
+            lease_strategy: self.lease_strategy,
+            queue: self.queue,
+            dispatch: self.dispatch,
+            reassembly: self.reassembly,
+            shutdown: signal.clone(),
+        });
+        loop {
+            let next = tokio::select! {
+                next = server_transport.next() => next,
+                _ = signal.wait() => break,
+            };
+            let next = match next {
+                Some(it) => it,
+                None => break,
+            };
             match next {
                 Ok(tp) => {
-                    let acceptor = acceptor.clone();
+                    let opts = opts.clone();
                     runtime::spawn(async move {
-                        if let Err(e) = Self::on_transport(mtu, tp, acceptor).await {
+                        if let Err(e) = Self::on_transport(tp, opts).await {
                             error!("handle transport failed: {}", e);
                         }
                     });
@@ -96,63 +229,168 @@ where
                 }
             }
         }
+        if self.shutdown.is_shutdown() {
+            // wait for the live connections to be closed.
+            drop(server_transport);
+            drop((opts, signal));
+            let _ = drained.recv().await;
+        }
         Ok(())
     }
 
     #[inline]
-    pub(crate) async fn on_transport(mtu: usize, tp: C, acceptor: Arc<Option<ServerResponder>>) -> Result<()> {
+    pub(crate) async fn on_transport(tp: C, opts: Arc<ServerOptions>) -> Result<()> {
         // Establish connection.
         let conn = tp.connect().await?;
+        let info = conn.info();
         let (mut writer, mut reader) = conn.split();
 
+        // The first frame tells whether a session is set up or resumed.
+        let first = match reader.next().await {
+            Some(next) => next?,
+            None => return Ok(()),
+        };
+        if let Body::Resume(v) = first.get_body_ref() {
+            match &opts.sessions {
+                Some(sessions) => sessions.clone().resume(v.clone(), writer, reader).await,
+                None => transport::reject_resume(writer, "resume is not supported").await,
+            }
+            return Ok(());
+        }
+        let (token, lifetime) = match first.get_body_ref() {
+            Body::Setup(v) => (v.get_token().cloned(), v.get_lifetime()),
+            _ => (None, DEFAULT_LIFETIME),
+        };
+        if token.is_some() && opts.sessions.is_none() {
+            let sending = frame::Error::builder(0, 0)
+                .set_code(error::ERR_UNSUPPORTED_SETUP)
+                .set_data(Bytes::from("resume is not supported"))
+                .build();
+            writer.send(sending).await?;
+            return Ok(());
+        }
+
         // Create frame splitter.
-        let splitter = if mtu != 0 {
-            Some(Splitter::new(mtu))
+        let splitter = if opts.mtu != 0 {
+            Some(Splitter::new(opts.mtu))
         } else {
             None
         };
 
         // Init duplex socket.
-        let (snd_tx, mut snd_rx) = mpsc::unbounded_channel::<Frame>();
-        let mut socket = DuplexSocket::new(0, snd_tx, splitter);
-
-        // Begin loop for writing frames.
-        runtime::spawn(async move {
-            while let Some(frame) = snd_rx.recv().await {
-                if let Err(e) = writer.send(frame).await {
-                    error!("write frame failed: {}", e);
-                    break;
-                }
-            }
-        });
+        let (snd_tx, snd_rx) = transport::outbound(opts.queue);
+        let (shutdown, shutdown_rx) = oneshot::channel::<()>();
+        let error_tx = snd_tx.clone();
+        let closing_tx = snd_tx.clone();
+        let mut socket = DuplexSocket::new(2, snd_tx, splitter);
+        socket.set_lease_strategy(opts.lease_strategy.clone());
+        socket.set_setup_timeout(Some(opts.setup_timeout));
+        socket.set_connection_info(info);
+        let disconnect = Arc::new(Notify::new());
+        if let Some(registry) = &opts.registry {
+            socket.set_registry(registry.clone(), disconnect.clone());
+        }
+        socket.set_dispatch_options(opts.dispatch);
+        socket.set_reassembly_options(opts.reassembly);
+
+        let (read_tx, mut read_rx) = mpsc::channel::<Frame>(transport::READ_AHEAD);
 
-        let (read_tx, mut read_rx) = mpsc::unbounded_channel::<Frame>();
+        let session = match (token, &opts.sessions) {
+            (Some(token), Some(sessions)) => {
+                let (session, handovers) = sessions.open(token.clone(), read_tx.clone(), lifetime);
+                Some((token, session, handovers))
+            }
+            _ => None,
+        };
+        let opened = session.as_ref().map(|(t, s, _)| (t.clone(), s.clone()));
 
-        runtime::spawn(async move {
-            loop {
-                match reader.next().await {
-                    Some(Ok(frame)) => {
-                        if let Err(e) = read_tx.send(frame) {
-                            error!("forward frame failed: {}", e);
-                            break;
+        // Begin loops for writing and reading frames.
+        match session {
+            Some((token, session, handovers)) => {
+                let state = Some(session.state().clone());
+                runtime::spawn(transport::write_loop(
+                    snd_rx,
+                    handovers,
+                    shutdown_rx,
+                    Some(writer),
+                    state,
+                    None,
+                ));
+                let sessions = opts.sessions.clone().unwrap();
+                runtime::spawn(sessions.serve(token, session, 0, reader));
+                drop(read_tx);
+            }
+            None => {
+                let (_, handovers) = mpsc::unbounded_channel();
+                runtime::spawn(transport::write_loop(
+                    snd_rx,
+                    handovers,
+                    shutdown_rx,
+                    Some(writer),
+                    None,
+                    None,
+                ));
+                runtime::spawn(async move {
+                    let liveness = Liveness::new();
+                    tokio::select! {
+                        _ = transport::read_loop(reader, &read_tx, None, &liveness) => {}
+                        _ = liveness.expired(lifetime) => {
+                            warn!("no frame received for {:?}, the connection is dead", lifetime);
+                            let sending = frame::Error::builder(0, 0)
+                                .set_code(error::ERR_CONN_FAILED)
+                                .set_data(Bytes::from("keepalive timeout"))
+                                .build();
+                            if let Err(e) = error_tx.send(sending) {
+                                debug!("send keepalive timeout failed: {}", e);
+                            }
                         }
+                        _ = read_tx.closed() => {}
                     }
-                    Some(Err(e)) => {
-                        error!("read frame failed: {}", e);
-                        break;
-                    }
-                    None => {
-                        break;
-                    }
-                }
+                });
             }
-        });
+        }
 
-        while let Some(frame) = read_rx.recv().await {
-            if let Err(e) = socket.dispatch(frame, acceptor.as_ref().as_ref()).await {
-                error!("dispatch frame failed: {}", e);
+        let acceptor = opts.acceptor.as_ref();
+        let mut signal = opts.shutdown.clone();
+        let mut draining: Option<Pin<Box<dyn Future<Output = ()> + Send>>> = None;
+        let mut next = Some(first);
+        while let Some(frame) = next {
+            if let Err(e) = socket.dispatch(frame, acceptor).await {
+                info!("connection terminated: {}", e);
                 break;
             }
+            next = loop {
+                tokio::select! {
+                    it = read_rx.recv() => break it,
+                    _ = disconnect.notified() => {
+                        info!("connection {} disconnected", socket.connection_id());
+                        // the writer flushes it before closing the connection.
+                        let sending = frame::Error::builder(0, 0)
+                            .set_code(error::ERR_CONN_FAILED)
+                            .set_data(Bytes::from("connection disconnected"))
+                            .build();
+                        if let Err(e) = closing_tx.send(sending) {
+                            debug!("send disconnect error failed: {}", e);
+                        }
+                        break None;
+                    }
+                    timeout = signal.wait(), if draining.is_none() => {
+                        draining = Some(Box::pin(socket.dispose(timeout)));
+                    }
+                    _ = async { draining.as_mut().unwrap().await }, if draining.is_some() => {
+                        break None;
+                    }
+                }
+            };
+        }
+        socket.fail_pending();
+        if let Some(registry) = &opts.registry {
+            registry.remove(socket.connection_id());
+        }
+        // stop the writer once the pending frames are flushed.
+        let _ = shutdown.send(());
+        if let (Some((token, session)), Some(sessions)) = (opened, &opts.sessions) {
+            sessions.remove(&token, &session);
         }
         Ok(())
     }
@@ -0,0 +1,76 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::{mpsc, watch};
+
+/// Stops a server gracefully.
+///
+/// Once triggered, the server stops accepting connections and sends ERROR[CONNECTION_CLOSE] on
+/// every live one. Each connection is closed when its interactions in flight have completed, or
+/// cancelled when the timeout elapses, then `serve` returns.
+#[derive(Clone, Debug)]
+pub struct ShutdownHandle {
+    trigger: Arc<watch::Sender<Option<Duration>>>,
+    // keeps the channel open while no server is running
+    triggered: watch::Receiver<Option<Duration>>,
+}
+
+/// Shutdown signal observed by a server and its connections, which are tracked by its clones.
+#[derive(Clone)]
+pub(crate) struct ShutdownSignal {
+    triggered: watch::Receiver<Option<Duration>>,
+    _live: mpsc::Sender<()>,
+}
+
+impl ShutdownHandle {
+    pub fn new() -> ShutdownHandle {
+        let (trigger, triggered) = watch::channel(None);
+        ShutdownHandle {
+            trigger: Arc::new(trigger),
+            triggered,
+        }
+    }
+
+    /// Triggers the shutdown, giving the interactions in flight `timeout` to complete.
+    pub fn shutdown(&self, timeout: Duration) {
+        if self.is_shutdown() {
+            return;
+        }
+        let _ = self.trigger.send(Some(timeout));
+    }
+
+    pub fn is_shutdown(&self) -> bool {
+        self.triggered.borrow().is_some()
+    }
+
+    /// Returns a signal for a new server, and a receiver which completes once the signal and
+    /// all of its clones are dropped.
+    pub(crate) fn signal(&self) -> (ShutdownSignal, mpsc::Receiver<()>) {
+        let (live, drained) = mpsc::channel(1);
+        let signal = ShutdownSignal {
+            triggered: self.triggered.clone(),
+            _live: live,
+        };
+        (signal, drained)
+    }
+}
+
+impl Default for ShutdownHandle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ShutdownSignal {
+    /// Waits for the shutdown, returning the time left to the interactions in flight.
+    pub(crate) async fn wait(&mut self) -> Duration {
+        loop {
+            if let Some(timeout) = *self.triggered.borrow() {
+                return timeout;
+            }
+            if self.triggered.changed().await.is_err() {
+                futures::future::pending::<()>().await;
+            }
+        }
+    }
+}
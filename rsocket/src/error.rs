@@ -1,8 +1,12 @@
 use std::fmt;
 use std::io;
+use std::time::Duration;
 
+use bytes::Bytes;
 use thiserror::Error;
 
+use crate::frame;
+
 pub const ERR_INVALID_SETUP: u32 = 0x0000_0001;
 pub const ERR_UNSUPPORTED_SETUP: u32 = 0x0000_0002;
 pub const ERR_REJECT_SETUP: u32 = 0x0000_0003;
@@ -13,6 +17,9 @@ pub const ERR_APPLICATION: u32 = 0x0000_0201;
 pub const ERR_REJECTED: u32 = 0x0000_0202;
 pub const ERR_CANCELED: u32 = 0x0000_0203;
 pub const ERR_INVALID: u32 = 0x0000_0204;
+/// Range of the error codes left to applications.
+pub const ERR_APPLICATION_CUSTOM_MIN: u32 = 0x0000_0301;
+pub const ERR_APPLICATION_CUSTOM_MAX: u32 = 0xFFFF_FFFE;
 
 #[derive(Error, Debug)]
 pub enum RSocketError {
@@ -23,7 +30,7 @@ pub enum RSocketError {
     UnsupportedSetup(String),
     #[error("REJECTED_SETUP: {0}")]
     RejectedSetup(String),
-    #[error("REJECTED_SETUP: {0}")]
+    #[error("REJECTED_RESUME: {0}")]
     RejectedResume(String),
     #[error("CONNECTION_ERROR: {0}")]
     ConnectionException(String),
@@ -39,6 +46,9 @@ pub enum RSocketError {
     RequestInvalid(String),
     #[error("RESERVED({0}): {1}")]
     Reserved(u32, String),
+    /// Application error with a code of its own and data of any format.
+    #[error("APPLICATION_ERROR({code:#x}): {}", String::from_utf8_lossy(.data))]
+    Custom { code: u32, data: Bytes },
 
     // Codec errors:
     #[error("this frame is incomplete")]
@@ -46,6 +56,8 @@ pub enum RSocketError {
     // Custom errors:
     #[error("{0}")]
     WithDescription(String),
+    #[error("request timed out after {0:?}")]
+    Timeout(Duration),
     #[error(transparent)]
     IO(#[from] io::Error),
     #[error(transparent)]
@@ -53,6 +65,91 @@ pub enum RSocketError {
 }
 
 impl RSocketError {
+    /// Application error with a custom code, between `ERR_APPLICATION_CUSTOM_MIN` and
+    /// `ERR_APPLICATION_CUSTOM_MAX`. Responders send other codes as APPLICATION_ERROR.
+    ///
+    /// # Example
+    /// ```
+    /// use rsocket_rust::error::RSocketError;
+    ///
+    /// let e = RSocketError::custom(0x301, r#"{"retry_after":5}"#);
+    /// assert_eq!(Some(0x301), e.code());
+    /// ```
+    pub fn custom(code: u32, data: impl Into<Bytes>) -> RSocketError {
+        RSocketError::Custom {
+            code,
+            data: data.into(),
+        }
+    }
+
+    /// Error code of the ERROR frame this error is carried by.
+    pub fn code(&self) -> Option<u32> {
+        let code = match self {
+            RSocketError::InvalidSetup(_) => ERR_INVALID_SETUP,
+            RSocketError::UnsupportedSetup(_) => ERR_UNSUPPORTED_SETUP,
+            RSocketError::RejectedSetup(_) => ERR_REJECT_SETUP,
+            RSocketError::RejectedResume(_) => ERR_REJECT_RESUME,
+            RSocketError::ConnectionException(_) => ERR_CONN_FAILED,
+            RSocketError::ConnectionClosed(_) => ERR_CONN_CLOSED,
+            RSocketError::ApplicationException(_) => ERR_APPLICATION,
+            RSocketError::RequestRejected(_) => ERR_REJECTED,
+            RSocketError::RequestCancelled(_) => ERR_CANCELED,
+            RSocketError::RequestInvalid(_) => ERR_INVALID,
+            RSocketError::Reserved(code, _) => *code,
+            RSocketError::Custom { code, .. } => *code,
+            _ => return None,
+        };
+        Some(code)
+    }
+
+    /// Data of the ERROR frame this error is carried by.
+    pub fn data(&self) -> Option<Bytes> {
+        match self {
+            RSocketError::InvalidSetup(desc)
+            | RSocketError::UnsupportedSetup(desc)
+            | RSocketError::RejectedSetup(desc)
+            | RSocketError::RejectedResume(desc)
+            | RSocketError::ConnectionException(desc)
+            | RSocketError::ConnectionClosed(desc)
+            | RSocketError::ApplicationException(desc)
+            | RSocketError::RequestRejected(desc)
+            | RSocketError::RequestCancelled(desc)
+            | RSocketError::RequestInvalid(desc)
+            | RSocketError::Reserved(_, desc) => Some(Bytes::from(desc.clone())),
+            RSocketError::Custom { data, .. } => Some(data.clone()),
+            _ => None,
+        }
+    }
+
+    /// Error received within an ERROR frame, custom ones keep their data as is.
+    pub(crate) fn from_frame(e: &frame::Error) -> Self {
+        let data = e.get_data().cloned().unwrap_or_default();
+        match e.get_code() {
+            code @ ERR_APPLICATION_CUSTOM_MIN..=ERR_APPLICATION_CUSTOM_MAX => {
+                RSocketError::Custom { code, data }
+            }
+            code => Self::must_new_from_code(code, String::from_utf8_lossy(&data).into_owned()),
+        }
+    }
+
+    /// Code and data of the ERROR frame terminating a stream on a failure of its handler.
+    pub(crate) fn to_stream_error(e: &anyhow::Error) -> (u32, Bytes) {
+        match e.downcast_ref::<RSocketError>() {
+            Some(RSocketError::ApplicationException(desc)) => {
+                (ERR_APPLICATION, desc.clone().into())
+            }
+            Some(RSocketError::RequestRejected(desc)) => (ERR_REJECTED, desc.clone().into()),
+            Some(RSocketError::RequestCancelled(desc)) => (ERR_CANCELED, desc.clone().into()),
+            Some(RSocketError::RequestInvalid(desc)) => (ERR_INVALID, desc.clone().into()),
+            Some(RSocketError::Custom { code, data })
+                if (ERR_APPLICATION_CUSTOM_MIN..=ERR_APPLICATION_CUSTOM_MAX).contains(code) =>
+            {
+                (*code, data.clone())
+            }
+            _ => (ERR_APPLICATION, Bytes::from(e.to_string())),
+        }
+    }
+
     pub(crate) fn must_new_from_code(code: u32, desc: String) -> Self {
         match code {
             ERR_APPLICATION => RSocketError::ApplicationException(desc),
@@ -0,0 +1,58 @@
+use std::time::Duration;
+
+use bytes::{Buf, BufMut, BytesMut};
+
+use super::composite::CompositeMetadata;
+use super::mime::MimeType;
+use crate::error::RSocketError;
+use crate::utils::Writeable;
+
+/// MIME type of the composite metadata entry carrying the deadline of a request.
+pub const DEADLINE_MIME_TYPE: &str = "message/x.rsocket.deadline.v0";
+
+/// Time budget left to the responder for a request, in milliseconds on the wire.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeadlineMetadata {
+    budget: Duration,
+}
+
+impl DeadlineMetadata {
+    pub fn new(budget: Duration) -> DeadlineMetadata {
+        DeadlineMetadata { budget }
+    }
+
+    pub fn get_budget(&self) -> Duration {
+        self.budget
+    }
+
+    pub fn mime_type() -> MimeType {
+        MimeType::from(DEADLINE_MIME_TYPE)
+    }
+
+    pub fn decode(bf: &mut BytesMut) -> crate::Result<DeadlineMetadata> {
+        if bf.len() < 8 {
+            return Err(RSocketError::WithDescription("require more bytes!".into()).into());
+        }
+        let budget = Duration::from_millis(bf.get_u64());
+        Ok(DeadlineMetadata { budget })
+    }
+
+    /// Finds the deadline entry among composite metadata.
+    pub fn find(metadata: &CompositeMetadata) -> Option<DeadlineMetadata> {
+        let mime_type = Self::mime_type();
+        metadata
+            .iter()
+            .find(|it| *it.get_mime_type() == mime_type)
+            .and_then(|it| Self::decode(&mut BytesMut::from(&it.get_metadata()[..])).ok())
+    }
+}
+
+impl Writeable for DeadlineMetadata {
+    fn write_to(&self, bf: &mut BytesMut) {
+        bf.put_u64(self.budget.as_millis() as u64);
+    }
+
+    fn len(&self) -> usize {
+        8
+    }
+}
@@ -1,7 +1,9 @@
 mod composite;
+mod deadline;
 mod mime;
 mod routing;
 
 pub use composite::{CompositeMetadata, CompositeMetadataBuilder, CompositeMetadataEntry};
+pub use deadline::{DeadlineMetadata, DEADLINE_MIME_TYPE};
 pub use mime::MimeType;
 pub use routing::{RoutingMetadata, RoutingMetadataBuilder};
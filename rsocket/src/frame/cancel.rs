@@ -1,6 +1,6 @@
 use super::{Body, Frame};
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub struct Cancel {}
 
 pub struct CancelBuilder {
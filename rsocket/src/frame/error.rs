@@ -6,7 +6,7 @@ use super::{Body, Frame};
 use crate::error::RSocketError;
 use crate::utils::Writeable;
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub struct Error {
     code: u32,
     data: Option<Bytes>,
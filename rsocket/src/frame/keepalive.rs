@@ -4,7 +4,7 @@ use super::{Body, Frame};
 use crate::error::RSocketError;
 use crate::utils::Writeable;
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub struct Keepalive {
     last_received_position: u64,
     data: Option<Bytes>,
@@ -68,6 +68,10 @@ impl Keepalive {
         self.last_received_position
     }
 
+    pub(crate) fn set_last_received_position(&mut self, position: u64) {
+        self.last_received_position = position;
+    }
+
     pub fn get_data(&self) -> Option<&Bytes> {
         self.data.as_ref()
     }
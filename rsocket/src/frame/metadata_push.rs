@@ -3,7 +3,7 @@ use bytes::{Buf, BufMut, Bytes, BytesMut};
 use super::{Body, Frame};
 use crate::utils::Writeable;
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub struct MetadataPush {
     metadata: Option<Bytes>,
 }
@@ -19,7 +19,9 @@ impl MetadataPushBuiler {
         MetadataPushBuiler {
             stream_id,
             flag,
-            value: MetadataPush { metadata: None },
+            value: MetadataPush {
+                metadata: Some(Bytes::new()),
+            },
         }
     }
 
@@ -28,8 +30,10 @@ impl MetadataPushBuiler {
         self
     }
 
+    /// Builds the frame, flagged with METADATA as it always carries metadata, empty or not.
     pub fn build(self) -> Frame {
-        Frame::new(self.stream_id, Body::MetadataPush(self.value), self.flag)
+        let flag = self.flag | Frame::FLAG_METADATA;
+        Frame::new(self.stream_id, Body::MetadataPush(self.value), flag)
     }
 }
 
@@ -1,7 +1,7 @@
 use bytes::{Buf, BufMut, Bytes, BytesMut};
 
 use crate::error::RSocketError;
-use crate::utils::Writeable;
+use crate::utils::{u24, Writeable};
 
 mod cancel;
 mod error;
@@ -40,7 +40,7 @@ pub const REQUEST_MAX: u32 = 0x7FFF_FFFF; // 2147483647
 
 pub(crate) const LEN_HEADER: usize = 6;
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub enum Body {
     Setup(Setup),
     Lease(Lease),
@@ -56,9 +56,11 @@ pub enum Body {
     MetadataPush(MetadataPush),
     Resume(Resume),
     ResumeOK(ResumeOK),
+    /// A frame of a type this implementation does not know, with its raw content.
+    Unknown(u16, Bytes),
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub struct Frame {
     pub(crate) stream_id: u32,
     pub(crate) body: Body,
@@ -110,6 +112,7 @@ impl Writeable for Frame {
             Body::Cancel() => (),
             Body::ResumeOK(v) => v.write_to(bf),
             Body::Resume(v) => v.write_to(bf),
+            Body::Unknown(_, v) => bf.put_slice(v),
         }
     }
 
@@ -131,6 +134,7 @@ impl Writeable for Frame {
                 Body::Error(v) => v.len(),
                 Body::ResumeOK(v) => v.len(),
                 Body::Resume(v) => v.len(),
+                Body::Unknown(_, v) => v.len(),
             }
     }
 }
@@ -168,7 +172,7 @@ impl Frame {
             Self::TYPE_ERROR => Error::decode(flag, b).map(Body::Error),
             Self::TYPE_RESUME_OK => ResumeOK::decode(flag, b).map(Body::ResumeOK),
             Self::TYPE_RESUME => Resume::decode(flag, b).map(Body::Resume),
-            typ => unreachable!("invalid frame type {}!", typ),
+            typ => Ok(Body::Unknown(typ, b.split().freeze())),
         };
         body.map(|it| Frame::new(sid, it, flag))
     }
@@ -207,6 +211,36 @@ impl Frame {
     pub fn has_complete(&self) -> bool {
         self.flag & Self::FLAG_COMPLETE != 0
     }
+
+    /// Writes the frame up to the metadata and data it carries, returning those instead of
+    /// copying them, in the order they follow.
+    ///
+    /// Frames without any payload are written whole. It lets transports hand large payloads
+    /// over to vectored writes.
+    pub fn write_head_to(&self, bf: &mut BytesMut) -> [Option<Bytes>; 2] {
+        let (metadata, data) = match &self.body {
+            Body::RequestResponse(v) => (v.get_metadata(), v.get_data()),
+            Body::RequestFNF(v) => (v.get_metadata(), v.get_data()),
+            Body::RequestStream(v) => (v.get_metadata(), v.get_data()),
+            Body::RequestChannel(v) => (v.get_metadata(), v.get_data()),
+            Body::Payload(v) => (v.get_metadata(), v.get_data()),
+            _ => {
+                self.write_to(bf);
+                return [None, None];
+            }
+        };
+        bf.put_u32(self.stream_id);
+        bf.put_u16((to_frame_type(&self.body) << 10) | self.flag);
+        match &self.body {
+            Body::RequestStream(v) => bf.put_u32(v.get_initial_request_n()),
+            Body::RequestChannel(v) => bf.put_u32(v.get_initial_request_n()),
+            _ => (),
+        }
+        if let Some(v) = metadata {
+            u24::from(v.len()).write_to(bf);
+        }
+        [metadata.cloned(), data.cloned()]
+    }
 }
 
 #[inline]
@@ -226,5 +260,6 @@ fn to_frame_type(body: &Body) -> u16 {
         Body::MetadataPush(_) => Frame::TYPE_METADATA_PUSH,
         Body::Resume(_) => Frame::TYPE_RESUME,
         Body::ResumeOK(_) => Frame::TYPE_RESUME_OK,
+        Body::Unknown(typ, _) => *typ,
     }
 }
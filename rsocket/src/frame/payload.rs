@@ -5,7 +5,7 @@ use super::{Body, Frame};
 use crate::utils::Writeable;
 use crate::Result;
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub struct Payload {
     metadata: Option<Bytes>,
     data: Option<Bytes>,
@@ -7,7 +7,7 @@ use super::{Body, Frame, Version};
 use crate::error::RSocketError;
 use crate::utils::{Writeable, DEFAULT_MIME_TYPE};
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub struct Setup {
     version: Version,
     keepalive: u32,
@@ -3,6 +3,11 @@ use bytes::Bytes;
 use super::misc::bytes_to_utf8;
 use crate::frame;
 
+/// Data and metadata of a request or a response.
+///
+/// Metadata is either absent or present, possibly empty: frames flag whether they carry it, so a
+/// payload carrying empty metadata only reads back the same on the other end. Data has no such
+/// flag, so empty data is no data: a payload built with it holds none, as the other end reads it.
 #[derive(Debug, Clone)]
 pub struct Payload {
     m: Option<Bytes>,
@@ -25,7 +30,7 @@ impl PayloadBuilder {
     where
         A: Into<Vec<u8>>,
     {
-        self.value.d = Some(Bytes::from(data.into()));
+        self.value.d = non_empty(Bytes::from(data.into()));
         self
     }
 
@@ -43,7 +48,7 @@ impl PayloadBuilder {
     }
 
     pub fn set_data_utf8(mut self, data: &str) -> Self {
-        self.value.d = Some(Bytes::from(data.to_owned()));
+        self.value.d = non_empty(Bytes::from(data.to_owned()));
         self
     }
 
@@ -55,7 +60,7 @@ impl PayloadBuilder {
 impl Payload {
     pub fn new(data: Option<Bytes>, metadata: Option<Bytes>) -> Payload {
         Payload {
-            d: data,
+            d: data.and_then(non_empty),
             m: metadata,
         }
     }
@@ -100,10 +105,18 @@ impl Payload {
     }
 }
 
+fn non_empty(data: Bytes) -> Option<Bytes> {
+    if data.is_empty() {
+        None
+    } else {
+        Some(data)
+    }
+}
+
 impl From<&'static str> for Payload {
     fn from(data: &'static str) -> Payload {
         Payload {
-            d: Some(Bytes::from(data)),
+            d: non_empty(Bytes::from(data)),
             m: None,
         }
     }
@@ -112,7 +125,7 @@ impl From<&'static str> for Payload {
 impl From<(&'static str, &'static str)> for Payload {
     fn from((data, metadata): (&'static str, &'static str)) -> Payload {
         Payload {
-            d: Some(Bytes::from(data)),
+            d: non_empty(Bytes::from(data)),
             m: Some(Bytes::from(metadata)),
         }
     }
@@ -1,18 +1,24 @@
+use std::sync::Arc;
 use std::time::Duration;
 
 use bytes::Bytes;
 
 use super::misc::bytes_to_utf8;
 use crate::frame::Setup;
+use crate::transport::ConnectionInfo;
 use crate::utils::DEFAULT_MIME_TYPE;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SetupPayload {
     m: Option<Bytes>,
     d: Option<Bytes>,
     keepalive: (Duration, Duration),
     mime_m: Option<Bytes>,
     mime_d: Option<Bytes>,
+    token: Option<Bytes>,
+    honor_lease: bool,
+    info: Option<Arc<ConnectionInfo>>,
+    connection_id: Option<u64>,
 }
 
 #[derive(Debug)]
@@ -35,6 +41,10 @@ impl SetupPayloadBuilder {
                 keepalive: (Duration::from_secs(20), Duration::from_secs(90)),
                 mime_m: Some(Bytes::from(DEFAULT_MIME_TYPE.to_owned())),
                 mime_d: Some(Bytes::from(DEFAULT_MIME_TYPE.to_owned())),
+                token: None,
+                honor_lease: false,
+                info: None,
+                connection_id: None,
             },
         }
     }
@@ -95,6 +105,16 @@ impl SetupPayloadBuilder {
         self
     }
 
+    pub fn set_honor_lease(mut self, honor_lease: bool) -> Self {
+        self.inner.honor_lease = honor_lease;
+        self
+    }
+
+    pub(crate) fn set_resume_token(mut self, token: Bytes) -> Self {
+        self.inner.token = Some(token);
+        self
+    }
+
     pub fn build(self) -> SetupPayload {
         self.inner
     }
@@ -128,6 +148,39 @@ impl SetupPayload {
     pub fn data_mime_type(&self) -> Option<&str> {
         bytes_to_utf8(&self.mime_d)
     }
+
+    /// Whether the requester only sends requests within the leases granted by the responder.
+    pub fn honor_lease(&self) -> bool {
+        self.honor_lease
+    }
+
+    pub(crate) fn set_honor_lease(&mut self, honor_lease: bool) {
+        self.honor_lease = honor_lease;
+    }
+
+    /// Token identifying a resumable session, if the peer asked for one.
+    pub fn resume_token(&self) -> Option<&Bytes> {
+        self.token.as_ref()
+    }
+
+    /// Information about the peer which sent the SETUP, if its transport exposes any.
+    pub fn connection_info(&self) -> Option<&ConnectionInfo> {
+        self.info.as_deref()
+    }
+
+    pub(crate) fn set_connection_info(&mut self, info: Option<Arc<ConnectionInfo>>) {
+        self.info = info;
+    }
+
+    /// Identifies the connection which received the SETUP within the process, as
+    /// `RequestContext::connection_id` does.
+    pub fn connection_id(&self) -> Option<u64> {
+        self.connection_id
+    }
+
+    pub(crate) fn set_connection_id(&mut self, id: u64) {
+        self.connection_id = Some(id);
+    }
 }
 
 impl From<Setup> for SetupPayload {
@@ -140,6 +193,9 @@ impl From<Setup> for SetupPayload {
         if let Some(m) = input.get_mime_metadata() {
             bu = bu.set_metadata_mime_type(m);
         }
+        if let Some(token) = input.get_token() {
+            bu = bu.set_resume_token(token.clone());
+        }
         let keepalive = (input.get_keepalive(), input.get_lifetime());
         let (d, m) = input.split();
         bu.inner.d = d;
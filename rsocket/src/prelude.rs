@@ -1,6 +1,10 @@
 pub use futures::{Sink, SinkExt, Stream, StreamExt};
 
-pub use crate::core::RSocketFactory;
+pub use crate::core::{ConnectionHandle, ConnectionRegistry, RSocketFactory, ShutdownHandle};
 pub use crate::payload::{Payload, PayloadBuilder, SetupPayload, SetupPayloadBuilder};
 pub use crate::spi::*;
-pub use crate::transport::{ServerTransport, Transport};
+pub use crate::transport::{
+    ConnectionInfo, DispatchOptions, InteractionType, PayloadBody, PeerIdentity, QueueDepth,
+    QueueOptions, ReassemblyOptions, RequestContext, ResumeOptions, ServerTransport, StreamInfo,
+    StreamOptions, StreamState, Subscription, Transport, TransportKind,
+};
@@ -1,7 +1,9 @@
 use std::future::Future;
 use std::pin::Pin;
+use std::time::Duration;
 
 use async_trait::async_trait;
+use bytes::Bytes;
 use futures::Stream;
 
 use crate::payload::{Payload, SetupPayload};
@@ -10,6 +12,21 @@ use crate::Result;
 pub type ClientResponder = Box<dyn Send + Sync + FnOnce() -> Box<dyn RSocket>>;
 pub type ServerResponder =
     Box<dyn Send + Sync + Fn(SetupPayload, Box<dyn RSocket>) -> Result<Box<dyn RSocket>>>;
+/// Acceptor which may wait for I/O, such as validating a token or dialing an upstream.
+pub type AsyncServerResponder = Box<
+    dyn Send
+        + Sync
+        + Fn(
+            SetupPayload,
+            Box<dyn RSocket>,
+        ) -> Pin<Box<dyn Send + Future<Output = Result<Box<dyn RSocket>>>>>,
+>;
+
+/// Acceptor of the connections set up by clients.
+pub(crate) enum Acceptor {
+    Sync(ServerResponder),
+    Async(AsyncServerResponder),
+}
 
 pub type Flux<T> = Pin<Box<dyn Send + Stream<Item = T>>>;
 
@@ -64,3 +81,56 @@ pub trait RSocket: Sync + Send {
     /// Request-Channel interaction model of RSocket.
     fn request_channel(&self, reqs: Flux<Result<Payload>>) -> Flux<Result<Payload>>;
 }
+
+/// A lease granted to the requester of a connection: it may send at most `number_of_requests`
+/// requests within `ttl`.
+#[derive(Debug, Clone)]
+pub struct Lease {
+    pub ttl: Duration,
+    pub number_of_requests: u32,
+    pub metadata: Option<Bytes>,
+}
+
+impl Lease {
+    pub fn new(ttl: Duration, number_of_requests: u32) -> Lease {
+        Lease {
+            ttl,
+            number_of_requests,
+            metadata: None,
+        }
+    }
+}
+
+/// Strategy deciding the leases a server grants to the connections honoring them.
+///
+/// A new lease is asked for each time the previous one expires.
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+/// use rsocket_rust::prelude::*;
+///
+/// // grant 100 requests per second, and stop granting any under heavy load.
+/// let strategy = |active: usize| {
+///     if active < 1000 {
+///         Some(Lease::new(Duration::from_secs(1), 100))
+///     } else {
+///         None
+///     }
+/// };
+/// ```
+pub trait LeaseStrategy: Sync + Send {
+    /// Returns the next lease to grant, `None` grants nothing for a while.
+    ///
+    /// `active` is the number of streams currently in flight on the connection.
+    fn next_lease(&self, active: usize) -> Option<Lease>;
+}
+
+impl<F> LeaseStrategy for F
+where
+    F: Fn(usize) -> Option<Lease> + Sync + Send,
+{
+    fn next_lease(&self, active: usize) -> Option<Lease> {
+        self(active)
+    }
+}
@@ -0,0 +1,171 @@
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use tokio::sync::watch;
+
+use super::fragmentation::PayloadBody;
+use super::outbound::Outbound;
+use super::spi::ConnectionInfo;
+use super::stream::InteractionType;
+use crate::payload::SetupPayload;
+
+tokio::task_local! {
+    static CONTEXT: RequestContext;
+}
+
+/// MIME types negotiated by the SETUP frame of a connection.
+#[derive(Debug, Default)]
+pub(crate) struct MimeTypes {
+    pub(crate) data: Option<String>,
+    pub(crate) metadata: Option<String>,
+}
+
+impl From<&SetupPayload> for MimeTypes {
+    fn from(setup: &SetupPayload) -> MimeTypes {
+        MimeTypes {
+            data: setup.data_mime_type().map(String::from),
+            metadata: setup.metadata_mime_type().map(String::from),
+        }
+    }
+}
+
+/// What the requests received on the same connection share.
+#[derive(Debug, Clone)]
+pub(crate) struct ConnectionScope {
+    pub(crate) id: u64,
+    pub(crate) mimes: Arc<MimeTypes>,
+    pub(crate) info: Option<Arc<ConnectionInfo>>,
+    pub(crate) outbound: Outbound,
+}
+
+/// Context of the request a responder is handling.
+///
+/// It is available to the handlers of the `RSocket` trait through `RequestContext::current`,
+/// including the streams they return while those are polled.
+///
+/// # Example
+/// ```
+/// use rsocket_rust::prelude::*;
+///
+/// async fn audit() {
+///     if let Some(ctx) = RequestContext::current() {
+///         println!("stream {} of connection {}", ctx.stream_id(), ctx.connection_id());
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct RequestContext {
+    stream_id: u32,
+    interaction: InteractionType,
+    connection: ConnectionScope,
+    deadline: Option<Instant>,
+    cancelled: watch::Receiver<bool>,
+    body: Option<Arc<Mutex<Option<PayloadBody>>>>,
+}
+
+impl RequestContext {
+    pub(crate) fn new(
+        stream_id: u32,
+        interaction: InteractionType,
+        connection: ConnectionScope,
+        deadline: Option<Instant>,
+        cancelled: watch::Receiver<bool>,
+    ) -> RequestContext {
+        RequestContext {
+            stream_id,
+            interaction,
+            connection,
+            deadline,
+            cancelled,
+            body: None,
+        }
+    }
+
+    /// Attaches the body of a fragmented request whose data is streamed.
+    pub(crate) fn with_body(mut self, body: PayloadBody) -> RequestContext {
+        self.body = Some(Arc::new(Mutex::new(Some(body))));
+        self
+    }
+
+    /// Context of the request handled by the current task, if any.
+    pub fn current() -> Option<RequestContext> {
+        CONTEXT.try_with(|it| it.clone()).ok()
+    }
+
+    /// Runs `f` with this context as the current one.
+    pub(crate) async fn scope<F: std::future::Future>(self, f: F) -> F::Output {
+        CONTEXT.scope(self, f).await
+    }
+
+    /// Stream of the request, zero for metadata push.
+    pub fn stream_id(&self) -> u32 {
+        self.stream_id
+    }
+
+    pub fn interaction(&self) -> InteractionType {
+        self.interaction
+    }
+
+    /// Identifies the connection the request was received on, unique within the process.
+    pub fn connection_id(&self) -> u64 {
+        self.connection.id
+    }
+
+    /// Information about the peer, if the transport of the connection exposes any.
+    pub fn connection_info(&self) -> Option<&ConnectionInfo> {
+        self.connection.info.as_deref()
+    }
+
+    pub fn data_mime_type(&self) -> Option<&str> {
+        self.connection.mimes.data.as_deref()
+    }
+
+    pub fn metadata_mime_type(&self) -> Option<&str> {
+        self.connection.mimes.metadata.as_deref()
+    }
+
+    /// Deadline propagated by the requester, after which the request gets aborted.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// Takes the data of the request, when it is fragmented and bodies are streamed.
+    ///
+    /// The payload given to the handler carries the metadata only then, see
+    /// `ReassemblyOptions::stream_bodies`. The body can be taken once.
+    pub fn take_body(&self) -> Option<PayloadBody> {
+        self.body.as_ref().and_then(|it| it.lock().unwrap().take())
+    }
+
+    /// Sets the share of the connection the responses of the request get, relative to the other
+    /// streams.
+    ///
+    /// Streams write their frames in turns, `weight` of them per turn, one by default. Frames
+    /// controlling the connection, like KEEPALIVE, are written ahead of any stream.
+    pub fn set_weight(&self, weight: u32) {
+        if self.stream_id != 0 {
+            self.connection.outbound.set_weight(self.stream_id, weight);
+        }
+    }
+
+    /// Whether the request has been cancelled: by the requester, on an error, once its deadline
+    /// elapsed or when the connection is gone.
+    pub fn is_cancelled(&self) -> bool {
+        *self.cancelled.borrow()
+    }
+
+    /// Resolves once the request gets cancelled, never if it completes instead.
+    ///
+    /// Fire-and-forget requests cannot be cancelled, so it never resolves for them.
+    ///
+    /// The handler itself is dropped right after the cancellation, so this is meant for tasks
+    /// it has spawned and for cleaning up external resources.
+    pub async fn cancelled(&self) {
+        let mut cancelled = self.cancelled.clone();
+        while !*cancelled.borrow() {
+            if cancelled.changed().await.is_err() {
+                futures::future::pending::<()>().await;
+            }
+        }
+    }
+}
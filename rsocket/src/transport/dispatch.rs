@@ -0,0 +1,103 @@
+use std::future::Future;
+use std::sync::Arc;
+
+use futures::future::BoxFuture;
+use tokio::sync::{mpsc, Semaphore};
+
+use crate::runtime;
+
+/// How the fire_and_forget and metadata_push handlers of a responder are run.
+///
+/// Handlers run apart from the frames of the connection, so a slow one does not hold up the
+/// other interactions. Once `concurrency` handlers are in flight, the connection waits for one of
+/// them to complete before reading more frames.
+///
+/// # Example
+/// ```
+/// use rsocket_rust::prelude::*;
+///
+/// // run the handlers one at a time, in the order the frames were received.
+/// let opts = DispatchOptions::new().ordered(true);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct DispatchOptions {
+    concurrency: usize,
+    ordered: bool,
+}
+
+impl Default for DispatchOptions {
+    fn default() -> DispatchOptions {
+        DispatchOptions {
+            concurrency: 256,
+            ordered: false,
+        }
+    }
+}
+
+impl DispatchOptions {
+    pub fn new() -> DispatchOptions {
+        DispatchOptions::default()
+    }
+
+    /// Sets the maximum of handlers in flight, or queued when ordered.
+    pub fn concurrency(mut self, n: usize) -> Self {
+        self.concurrency = n.max(1);
+        self
+    }
+
+    /// Runs the handlers one after another in the order their frames were received.
+    pub fn ordered(mut self, ordered: bool) -> Self {
+        self.ordered = ordered;
+        self
+    }
+
+    pub fn get_concurrency(&self) -> usize {
+        self.concurrency
+    }
+
+    pub fn is_ordered(&self) -> bool {
+        self.ordered
+    }
+}
+
+/// Runs the handlers of a connection according to its `DispatchOptions`.
+pub(crate) enum Executor {
+    Concurrent(Arc<Semaphore>),
+    Ordered(mpsc::Sender<BoxFuture<'static, ()>>),
+}
+
+impl Executor {
+    pub(crate) fn new(opts: DispatchOptions) -> Executor {
+        if !opts.ordered {
+            return Executor::Concurrent(Arc::new(Semaphore::new(opts.concurrency)));
+        }
+        let (tx, mut rx) = mpsc::channel::<BoxFuture<'static, ()>>(opts.concurrency);
+        runtime::spawn(async move {
+            while let Some(task) = rx.recv().await {
+                task.await;
+            }
+        });
+        Executor::Ordered(tx)
+    }
+
+    /// Schedules a handler, waiting while the limit of handlers in flight is reached.
+    pub(crate) async fn execute(&self, task: impl Future<Output = ()> + Send + 'static) {
+        match self {
+            Executor::Concurrent(limit) => {
+                let permit = match limit.clone().acquire_owned().await {
+                    Ok(it) => it,
+                    Err(_) => return,
+                };
+                runtime::spawn(async move {
+                    task.await;
+                    drop(permit);
+                });
+            }
+            Executor::Ordered(tx) => {
+                if tx.send(Box::pin(task)).await.is_err() {
+                    error!("schedule handler failed: executor is gone");
+                }
+            }
+        }
+    }
+}
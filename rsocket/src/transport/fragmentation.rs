@@ -1,14 +1,208 @@
 use std::collections::LinkedList;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
 
 use bytes::{Buf, BufMut, Bytes, BytesMut};
+use futures::Stream;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::sync::{mpsc, Semaphore};
 
 use crate::frame::{self, Body, Frame};
 use crate::payload::Payload;
+use crate::Result;
 
 pub(crate) const MIN_MTU: usize = 64;
 
+/// Size of the data of the fragments cut from a body when no mtu is set.
+pub(crate) const BODY_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Bounds on the fragments a connection holds on to while reassembling them.
+///
+/// A stream whose reassembled payload would exceed `max_payload_size` is cancelled with an
+/// ERROR[CANCELED], a peer fragmenting more than `max_partial_streams` payloads at once gets
+/// the connection closed with an ERROR[CONNECTION_ERROR].
+///
+/// With `stream_bodies`, fragmented request-response and fire-and-forget requests are not
+/// reassembled: they are handed to the responder as soon as their metadata is received, and
+/// their data follows as a `PayloadBody` taken from the `RequestContext`. The metadata counts
+/// towards `max_payload_size` then, and the body buffers up to `max_payload_size` of data not
+/// consumed yet. Requests of streams and channels are reassembled regardless.
+///
+/// # Example
+/// ```
+/// use rsocket_rust::prelude::*;
+///
+/// // reassemble payloads of up to 1MiB, on 16 streams at most.
+/// let opts = ReassemblyOptions::new()
+///     .max_payload_size(1024 * 1024)
+///     .max_partial_streams(16);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct ReassemblyOptions {
+    max_payload_size: usize,
+    max_partial_streams: usize,
+    stream_bodies: bool,
+}
+
+impl Default for ReassemblyOptions {
+    fn default() -> ReassemblyOptions {
+        ReassemblyOptions {
+            max_payload_size: 16 * 1024 * 1024,
+            max_partial_streams: 256,
+            stream_bodies: false,
+        }
+    }
+}
+
+impl ReassemblyOptions {
+    pub fn new() -> ReassemblyOptions {
+        ReassemblyOptions::default()
+    }
+
+    /// Sets the maximum size of a reassembled payload, data and metadata included.
+    pub fn max_payload_size(mut self, size: usize) -> Self {
+        self.max_payload_size = size;
+        self
+    }
+
+    /// Sets the maximum of streams with fragments being reassembled at once.
+    pub fn max_partial_streams(mut self, n: usize) -> Self {
+        self.max_partial_streams = n.max(1);
+        self
+    }
+
+    /// Streams the data of fragmented request-response and fire-and-forget requests to the
+    /// responder instead of reassembling it.
+    pub fn stream_bodies(mut self, enabled: bool) -> Self {
+        self.stream_bodies = enabled;
+        self
+    }
+
+    pub fn get_max_payload_size(&self) -> usize {
+        self.max_payload_size
+    }
+
+    pub fn get_max_partial_streams(&self) -> usize {
+        self.max_partial_streams
+    }
+
+    pub fn is_streaming_bodies(&self) -> bool {
+        self.stream_bodies
+    }
+}
+
+/// Data of a fragmented request, received chunk by chunk as its fragments arrive.
+///
+/// The body ends once its last fragment is received, or fails if the stream is terminated
+/// before. Data not consumed yet is buffered up to `ReassemblyOptions::max_payload_size`,
+/// beyond which the connection stops reading frames until the body is consumed, slowing the
+/// requester down. A dropped body discards the rest of the data.
+///
+/// # Example
+/// ```no_run,ignore
+/// async fn fire_and_forget(&self, req: Payload) -> Result<()> {
+///     let mut file = File::create("upload.bin").await?;
+///     if let Some(mut body) = RequestContext::current().and_then(|it| it.take_body()) {
+///         while let Some(chunk) = body.next().await {
+///             file.write_all(&chunk?).await?;
+///         }
+///     }
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct PayloadBody {
+    rx: mpsc::UnboundedReceiver<Result<Bytes>>,
+    room: Arc<Semaphore>,
+    limit: usize,
+}
+
+/// Delivering end of a `PayloadBody`, which buffers data up to a limit.
+#[derive(Debug, Clone)]
+pub(crate) struct BodySender {
+    tx: mpsc::UnboundedSender<Result<Bytes>>,
+    /// One permit per byte the body may buffer yet
+    room: Arc<Semaphore>,
+    limit: usize,
+}
+
+impl PayloadBody {
+    /// Creates both ends of a body, which buffers at most `limit` bytes not consumed yet.
+    pub(crate) fn channel(limit: usize) -> (BodySender, PayloadBody) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let limit = limit.clamp(1, u32::MAX as usize);
+        let room = Arc::new(Semaphore::new(limit));
+        let sender = BodySender {
+            tx,
+            room: room.clone(),
+            limit,
+        };
+        (sender, PayloadBody { rx, room, limit })
+    }
+}
+
+impl BodySender {
+    /// Hands a chunk to the body, waiting for the data buffered to leave room for it.
+    ///
+    /// A chunk larger than the limit waits for the body to be consumed entirely.
+    pub(crate) async fn deliver(&self, chunk: Bytes) {
+        let permits = chunk.len().min(self.limit) as u32;
+        tokio::select! {
+            acquired = self.room.acquire_many(permits) => match acquired {
+                Ok(it) => it.forget(),
+                Err(_) => return,
+            },
+            // the body has been dropped, the chunk is discarded.
+            _ = self.tx.closed() => return,
+        }
+        let _ = self.tx.send(Ok(chunk));
+    }
+
+    /// Fails the body, after the chunks buffered so far.
+    pub(crate) fn fail(&self, e: anyhow::Error) {
+        // a chunk waiting for room is discarded.
+        self.room.close();
+        let _ = self.tx.send(Err(e));
+    }
+}
+
+impl Stream for PayloadBody {
+    type Item = Result<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let polled = self.rx.poll_recv(cx);
+        if let Poll::Ready(Some(Ok(chunk))) = &polled {
+            self.room.add_permits(chunk.len().min(self.limit));
+        }
+        polled
+    }
+}
+
+/// Reads the next chunk of a body into `buf`, short only once the end of the body is reached.
+///
+/// The chunk is split off `buf`, whose memory gets reused for the next one once the chunk is
+/// dropped.
+pub(crate) async fn read_chunk<R>(body: &mut R, buf: &mut BytesMut, size: usize) -> Result<Bytes>
+where
+    R: AsyncRead + Unpin,
+{
+    buf.reserve(size);
+    while buf.len() < size {
+        let room = size - buf.len();
+        if body.read_buf(&mut (&mut *buf).limit(room)).await? == 0 {
+            break;
+        }
+    }
+    Ok(buf.split().freeze())
+}
+
 pub(crate) struct Joiner {
     inner: LinkedList<Frame>,
+    /// Size of the data and metadata of the fragments so far
+    size: usize,
+    /// Set once a fragment came without metadata or with data, which follows the metadata
+    metadata_complete: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -22,6 +216,10 @@ impl Splitter {
         Splitter { mtu }
     }
 
+    pub(crate) fn mtu(&self) -> usize {
+        self.mtu
+    }
+
     pub(crate) fn cut(&self, input: Payload, skip: usize) -> impl Iterator<Item = Payload> {
         let (data, meta) = input.split();
         SplitterIter {
@@ -51,6 +249,8 @@ impl Iterator for SplitterIter {
         let mut d: Option<Bytes> = None;
         let mut left = self.mtu - frame::LEN_HEADER - self.skip;
         if let Some(it) = &mut self.meta {
+            // the length of the metadata comes before it.
+            left -= 3;
             let msize = it.len();
             if left < msize {
                 m = Some(it.split_to(left));
@@ -80,6 +280,8 @@ impl Into<Payload> for Joiner {
     fn into(self) -> Payload {
         let mut bf = BytesMut::new();
         let mut bf2 = BytesMut::new();
+        // metadata may be present but empty, as flagged by the frames.
+        let mut has_metadata = false;
         self.inner.into_iter().for_each(|it: Frame| {
             let (d, m) = match it.body {
                 Body::RequestResponse(body) => body.split(),
@@ -93,6 +295,7 @@ impl Into<Payload> for Joiner {
                 bf.put(raw);
             }
             if let Some(raw) = m {
+                has_metadata = true;
                 bf2.put(raw);
             }
         });
@@ -102,10 +305,10 @@ impl Into<Payload> for Joiner {
         } else {
             Some(bf.freeze())
         };
-        let metadata = if bf2.is_empty() {
-            None
-        } else {
+        let metadata = if has_metadata {
             Some(bf2.freeze())
+        } else {
+            None
         };
         Payload::new(data, metadata)
     }
@@ -115,6 +318,8 @@ impl Joiner {
     pub(crate) fn new() -> Joiner {
         Joiner {
             inner: LinkedList::new(),
+            size: 0,
+            metadata_complete: false,
         }
     }
 
@@ -131,8 +336,39 @@ impl Joiner {
     }
 
     pub(crate) fn push(&mut self, next: Frame) {
+        let (d, m) = match &next.body {
+            Body::RequestResponse(body) => (body.get_data(), body.get_metadata()),
+            Body::RequestStream(body) => (body.get_data(), body.get_metadata()),
+            Body::RequestChannel(body) => (body.get_data(), body.get_metadata()),
+            Body::RequestFNF(body) => (body.get_data(), body.get_metadata()),
+            Body::Payload(body) => (body.get_data(), body.get_metadata()),
+            _ => (None, None),
+        };
+        self.size += d.map_or(0, |it| it.len()) + m.map_or(0, |it| it.len());
+        if next.flag & Frame::FLAG_METADATA == 0 || d.is_some() {
+            self.metadata_complete = true;
+        }
         self.inner.push_back(next);
     }
+
+    /// Whether the fragments are those of a request whose data can be streamed as a body,
+    /// which only request-response and fire-and-forget have.
+    pub(crate) fn has_body(&self) -> bool {
+        matches!(
+            self.first().body,
+            Body::RequestResponse(_) | Body::RequestFNF(_)
+        )
+    }
+
+    /// Whether the metadata has been received in full, the rest of the fragments carrying data.
+    pub(crate) fn has_complete_metadata(&self) -> bool {
+        self.metadata_complete
+    }
+
+    /// Size of the payload reassembled so far.
+    pub(crate) fn len(&self) -> usize {
+        self.size
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,45 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::error::RSocketError;
+use crate::Result;
+
+/// Tracks the lease currently in effect for one direction of a connection.
+///
+/// Leasing is off until enabled, then requests are only allowed while a lease is valid.
+#[derive(Debug, Default)]
+pub(crate) struct LeaseTracker {
+    enabled: AtomicBool,
+    // expiration and remaining requests of the current lease
+    current: Mutex<Option<(Instant, u32)>>,
+}
+
+impl LeaseTracker {
+    pub(crate) fn enable(&self) {
+        self.enabled.store(true, Ordering::SeqCst);
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn grant(&self, ttl: Duration, number_of_requests: u32) {
+        *self.current.lock().unwrap() = Some((Instant::now() + ttl, number_of_requests));
+    }
+
+    /// Uses up one request of the current lease.
+    pub(crate) fn try_acquire(&self) -> Result<()> {
+        if !self.is_enabled() {
+            return Ok(());
+        }
+        let mut current = self.current.lock().unwrap();
+        match current.as_mut() {
+            Some((expiration, n)) if *n > 0 && Instant::now() < *expiration => {
+                *n -= 1;
+                Ok(())
+            }
+            _ => Err(RSocketError::RequestRejected("no lease available".into()).into()),
+        }
+    }
+}
@@ -1,7 +1,10 @@
-use std::sync::atomic::{AtomicI64, AtomicU32, Ordering};
+use std::sync::atomic::{AtomicI64, AtomicU32, AtomicU64, Ordering};
 use std::sync::Arc;
 
-use crate::frame::Frame;
+use tokio::sync::Notify;
+use tokio::time::{Duration, Instant};
+
+use crate::frame::{Frame, REQUEST_MAX};
 
 #[derive(Debug, Clone)]
 pub(crate) struct StreamID {
@@ -43,6 +46,53 @@ impl Counter {
     }
 }
 
+/// Outstanding demand granted by the peer through REQUEST_N for one stream.
+///
+/// A total demand of `REQUEST_MAX` or more is treated as unbounded.
+#[derive(Debug)]
+pub(crate) struct Credit {
+    remaining: AtomicU64,
+    notify: Notify,
+}
+
+impl Credit {
+    pub(crate) fn new(n: u32) -> Credit {
+        Credit {
+            remaining: AtomicU64::new(n as u64),
+            notify: Notify::new(),
+        }
+    }
+
+    pub(crate) fn add(&self, n: u32) {
+        if n == 0 {
+            return;
+        }
+        let _ = self
+            .remaining
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |v| {
+                Some(v.saturating_add(n as u64))
+            });
+        self.notify.notify_one();
+    }
+
+    pub(crate) fn try_acquire(&self) -> bool {
+        self.remaining
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |v| match v {
+                0 => None,
+                v if v >= REQUEST_MAX as u64 => Some(v),
+                v => Some(v - 1),
+            })
+            .is_ok()
+    }
+
+    /// Waits until the peer has requested at least one more element, then consumes it.
+    pub(crate) async fn acquire(&self) {
+        while !self.try_acquire() {
+            self.notify.notified().await;
+        }
+    }
+}
+
 #[inline]
 pub(crate) fn debug_frame(snd: bool, f: &Frame) {
     if snd {
@@ -51,3 +101,41 @@ pub(crate) fn debug_frame(snd: bool, f: &Frame) {
         debug!("<=== RCV: {:?}", f);
     }
 }
+
+/// Tracks when the last frame has been received from the peer.
+#[derive(Debug)]
+pub(crate) struct Liveness {
+    started: Instant,
+    // milliseconds since started
+    last_seen: AtomicU64,
+}
+
+impl Liveness {
+    pub(crate) fn new() -> Liveness {
+        Liveness {
+            started: Instant::now(),
+            last_seen: AtomicU64::new(0),
+        }
+    }
+
+    pub(crate) fn touch(&self) {
+        let now = self.started.elapsed().as_millis() as u64;
+        self.last_seen.store(now, Ordering::Relaxed);
+    }
+
+    pub(crate) fn idle(&self) -> Duration {
+        let last_seen = Duration::from_millis(self.last_seen.load(Ordering::Relaxed));
+        self.started.elapsed().saturating_sub(last_seen)
+    }
+
+    /// Resolves once nothing has been received for `lifetime`.
+    pub(crate) async fn expired(&self, lifetime: Duration) {
+        loop {
+            let idle = self.idle();
+            if idle >= lifetime {
+                return;
+            }
+            tokio::time::sleep(lifetime - idle).await;
+        }
+    }
+}
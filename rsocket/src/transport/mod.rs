@@ -1,8 +1,32 @@
+mod context;
+mod dispatch;
 mod fragmentation;
+mod lease;
 mod misc;
+mod outbound;
+mod protocol;
+mod rtt;
+mod session;
 mod socket;
 mod spi;
+mod stream;
+mod subscription;
 
+pub use context::RequestContext;
+pub use dispatch::DispatchOptions;
+pub(crate) use dispatch::Executor;
 pub(crate) use fragmentation::{Joiner, Splitter, MIN_MTU};
-pub(crate) use socket::{ClientRequester,DuplexSocket};
+pub use fragmentation::{PayloadBody, ReassemblyOptions};
+pub(crate) use misc::Liveness;
+pub(crate) use outbound::{outbound, Outbound, OutboundReceiver};
+pub use outbound::{QueueDepth, QueueOptions};
+pub(crate) use rtt::Rtt;
+pub use session::ResumeOptions;
+pub(crate) use session::{
+    generate_token, read_loop, reject_resume, resume_session, write_loop, Handover, ResumeState,
+    SessionStore, SharedResumeState, READ_AHEAD,
+};
+pub(crate) use socket::{ClientRequester, DuplexSocket};
 pub use spi::*;
+pub use stream::{InteractionType, StreamInfo, StreamState};
+pub use subscription::{StreamOptions, Subscription};
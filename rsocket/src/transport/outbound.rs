@@ -0,0 +1,271 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use dashmap::DashMap;
+use futures::FutureExt;
+use tokio::sync::mpsc::error::SendError;
+use tokio::sync::{mpsc, watch};
+
+use crate::frame::{Body, Frame};
+use crate::utils::Writeable;
+
+/// Bounds of the queue of frames waiting to be written to a connection.
+///
+/// Requests and responder streams wait for room before sending, while frames controlling the
+/// connection or the streams in flight are always queued. Both bounds are unlimited by default.
+///
+/// The bounds are soft: senders which found room at the same time all queue their frame, so the
+/// queue may exceed them by about one frame per concurrent sender.
+///
+/// # Example
+/// ```
+/// use rsocket_rust::prelude::*;
+///
+/// let opts = QueueOptions::new().max_frames(1024).max_bytes(4 * 1024 * 1024);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct QueueOptions {
+    max_frames: usize,
+    max_bytes: usize,
+}
+
+/// Frames queued for a connection and their encoded size.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueueDepth {
+    pub frames: usize,
+    pub bytes: usize,
+}
+
+impl Default for QueueOptions {
+    fn default() -> QueueOptions {
+        QueueOptions {
+            max_frames: usize::MAX,
+            max_bytes: usize::MAX,
+        }
+    }
+}
+
+impl QueueOptions {
+    pub fn new() -> QueueOptions {
+        QueueOptions::default()
+    }
+
+    pub fn max_frames(mut self, n: usize) -> Self {
+        self.max_frames = n.max(1);
+        self
+    }
+
+    pub fn max_bytes(mut self, n: usize) -> Self {
+        self.max_bytes = n.max(1);
+        self
+    }
+
+    pub fn get_max_frames(&self) -> usize {
+        self.max_frames
+    }
+
+    pub fn get_max_bytes(&self) -> usize {
+        self.max_bytes
+    }
+}
+
+#[derive(Debug)]
+struct Queue {
+    opts: QueueOptions,
+    frames: AtomicUsize,
+    bytes: AtomicUsize,
+    // set once the writer is gone, so that nobody waits for room anymore
+    closed: AtomicBool,
+    dequeued: watch::Sender<()>,
+    // keeps the channel open while nobody is waiting
+    watching: watch::Receiver<()>,
+    // frames a stream may write in a row, one unless set otherwise.
+    weights: DashMap<u32, u32>,
+}
+
+/// Sending half of the outbound queue of a connection.
+#[derive(Debug, Clone)]
+pub(crate) struct Outbound {
+    tx: mpsc::UnboundedSender<Frame>,
+    queue: Arc<Queue>,
+}
+
+/// Receiving half of the outbound queue, drained by the writer.
+///
+/// Frames controlling the connection, like KEEPALIVE, ERROR and LEASE, are written first. Streams
+/// then take turns in a weighted round-robin, so that a burst on one of them does not hold back
+/// the others, metadata pushes taking theirs as if they were a stream. Frames of a single stream
+/// keep their order.
+#[derive(Debug)]
+pub(crate) struct OutboundReceiver {
+    rx: mpsc::UnboundedReceiver<Frame>,
+    queue: Arc<Queue>,
+    urgent: VecDeque<Frame>,
+    pending: HashMap<u32, VecDeque<Frame>>,
+    // streams with pending frames, the one whose turn it is first.
+    turns: VecDeque<u32>,
+    // frames written by the stream whose turn it is.
+    served: u32,
+}
+
+pub(crate) fn outbound(opts: QueueOptions) -> (Outbound, OutboundReceiver) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let (dequeued, watching) = watch::channel(());
+    let queue = Arc::new(Queue {
+        opts,
+        frames: AtomicUsize::new(0),
+        bytes: AtomicUsize::new(0),
+        closed: AtomicBool::new(false),
+        dequeued,
+        watching,
+        weights: DashMap::new(),
+    });
+    let sender = Outbound {
+        tx,
+        queue: queue.clone(),
+    };
+    let receiver = OutboundReceiver {
+        rx,
+        queue,
+        urgent: VecDeque::new(),
+        pending: HashMap::new(),
+        turns: VecDeque::new(),
+        served: 0,
+    };
+    (sender, receiver)
+}
+
+impl Queue {
+    fn is_full(&self) -> bool {
+        self.frames.load(Ordering::SeqCst) >= self.opts.max_frames
+            || self.bytes.load(Ordering::SeqCst) >= self.opts.max_bytes
+    }
+
+    fn add(&self, len: usize) {
+        self.frames.fetch_add(1, Ordering::SeqCst);
+        self.bytes.fetch_add(len, Ordering::SeqCst);
+    }
+
+    fn remove(&self, len: usize) {
+        self.frames.fetch_sub(1, Ordering::SeqCst);
+        self.bytes.fetch_sub(len, Ordering::SeqCst);
+    }
+}
+
+impl Outbound {
+    /// Queues a frame regardless of the bounds.
+    pub(crate) fn send(&self, frame: Frame) -> Result<(), Box<SendError<Frame>>> {
+        let len = frame.len();
+        self.queue.add(len);
+        self.tx
+            .send(frame)
+            .inspect_err(|_| self.queue.remove(len))
+            .map_err(Box::new)
+    }
+
+    /// Waits until the queue has room for more frames, without reserving it.
+    pub(crate) async fn ready(&self) {
+        let mut dequeued = self.queue.watching.clone();
+        while self.queue.is_full() && !self.queue.closed.load(Ordering::SeqCst) {
+            if dequeued.changed().await.is_err() {
+                break;
+            }
+        }
+    }
+
+    /// Waits until every queued frame has been taken by the writer, or the writer is gone.
+    pub(crate) async fn flushed(&self) {
+        let mut dequeued = self.queue.watching.clone();
+        while self.queue.frames.load(Ordering::SeqCst) > 0
+            && !self.queue.closed.load(Ordering::SeqCst)
+        {
+            if dequeued.changed().await.is_err() {
+                break;
+            }
+        }
+    }
+
+    /// Sets the frames a stream may write in a row before the next one takes its turn.
+    pub(crate) fn set_weight(&self, sid: u32, weight: u32) {
+        if weight > 1 {
+            self.queue.weights.insert(sid, weight);
+        } else {
+            self.queue.weights.remove(&sid);
+        }
+    }
+
+    pub(crate) fn forget(&self, sid: u32) {
+        self.queue.weights.remove(&sid);
+    }
+
+    pub(crate) fn depth(&self) -> QueueDepth {
+        QueueDepth {
+            frames: self.queue.frames.load(Ordering::SeqCst),
+            bytes: self.queue.bytes.load(Ordering::SeqCst),
+        }
+    }
+}
+
+impl OutboundReceiver {
+    pub(crate) async fn recv(&mut self) -> Option<Frame> {
+        if self.urgent.is_empty() && self.turns.is_empty() {
+            let frame = self.rx.recv().await?;
+            self.enqueue(frame);
+        }
+        // take everything queued so far, so that streams get their turns against each other.
+        while let Some(Some(frame)) = self.rx.recv().now_or_never() {
+            self.enqueue(frame);
+        }
+        let frame = self.dequeue()?;
+        self.queue.remove(frame.len());
+        let _ = self.queue.dequeued.send(());
+        Some(frame)
+    }
+
+    fn enqueue(&mut self, frame: Frame) {
+        let sid = frame.get_stream_id();
+        if sid == 0 && !matches!(frame.body, Body::MetadataPush(_)) {
+            self.urgent.push_back(frame);
+            return;
+        }
+        let turns = &mut self.turns;
+        self.pending
+            .entry(sid)
+            .or_insert_with(|| {
+                turns.push_back(sid);
+                VecDeque::new()
+            })
+            .push_back(frame);
+    }
+
+    fn dequeue(&mut self) -> Option<Frame> {
+        if let Some(frame) = self.urgent.pop_front() {
+            return Some(frame);
+        }
+        let sid = *self.turns.front()?;
+        let frames = self.pending.get_mut(&sid)?;
+        let frame = frames.pop_front();
+        self.served += 1;
+        if frames.is_empty() {
+            self.pending.remove(&sid);
+            self.turns.pop_front();
+            self.served = 0;
+        } else if self.served >= self.weight(sid) {
+            self.turns.rotate_left(1);
+            self.served = 0;
+        }
+        frame
+    }
+
+    fn weight(&self, sid: u32) -> u32 {
+        self.queue.weights.get(&sid).map_or(1, |it| *it)
+    }
+}
+
+impl Drop for OutboundReceiver {
+    fn drop(&mut self) {
+        self.queue.closed.store(true, Ordering::SeqCst);
+        let _ = self.queue.dequeued.send(());
+    }
+}
@@ -0,0 +1,144 @@
+use crate::error::{ERR_CONN_FAILED, ERR_INVALID, ERR_INVALID_SETUP};
+use crate::frame::{Body, Frame};
+
+/// Why an inbound frame does not get processed.
+#[derive(Debug)]
+pub(crate) enum Violation {
+    /// The frame is dropped silently, such as frames of streams which have terminated.
+    Ignore(String),
+    /// The stream is terminated with an ERROR carrying the code.
+    Stream(u32, u32, String),
+    /// The connection is closed after an ERROR carrying the code.
+    Connection(u32, String),
+}
+
+/// Checks the frames received on a connection against the rules of the protocol.
+#[derive(Debug)]
+pub(crate) struct Validator {
+    server: bool,
+    setup: bool,
+    // the highest stream opened by the peer so far
+    last_stream_id: u32,
+}
+
+impl Validator {
+    pub(crate) fn new(server: bool) -> Validator {
+        Validator {
+            server,
+            setup: false,
+            last_stream_id: 0,
+        }
+    }
+
+    /// Validates an inbound frame before it is reassembled and processed.
+    ///
+    /// Flags which are not defined for the type of the frame are reserved, so they get cleared
+    /// instead of being interpreted.
+    pub(crate) fn check(
+        &mut self,
+        frame: &mut Frame,
+        is_active: impl Fn(u32) -> bool,
+    ) -> Result<(), Violation> {
+        let sid = frame.stream_id;
+        if let Body::Unknown(typ, _) = &frame.body {
+            let reason = format!("unknown frame type {:#04x}", typ);
+            return if frame.flag & Frame::FLAG_IGNORE != 0 {
+                Err(Violation::Ignore(reason))
+            } else {
+                Err(Violation::Connection(ERR_CONN_FAILED, reason))
+            };
+        }
+        frame.flag &= defined_flags(&frame.body);
+
+        if self.server && !self.setup && !matches!(frame.body, Body::Setup(_)) {
+            return Err(Violation::Connection(
+                ERR_INVALID_SETUP,
+                "the first frame must be SETUP".into(),
+            ));
+        }
+
+        match &frame.body {
+            Body::Setup(_) => {
+                if !self.server || self.setup {
+                    return connection_error("unexpected SETUP frame");
+                }
+                if sid != 0 {
+                    return Err(Violation::Connection(
+                        ERR_INVALID_SETUP,
+                        format!("SETUP frame on stream {}", sid),
+                    ));
+                }
+                self.setup = true;
+            }
+            Body::Resume(_) | Body::ResumeOK(_) => {
+                return connection_error("unexpected RESUME frame on an established connection");
+            }
+            Body::Lease(_) | Body::Keepalive(_) | Body::MetadataPush(_) => {
+                if sid != 0 {
+                    return connection_error(&format!("connection frame on stream {}", sid));
+                }
+            }
+            Body::RequestFNF(_)
+            | Body::RequestResponse(_)
+            | Body::RequestStream(_)
+            | Body::RequestChannel(_) => {
+                if sid == 0 {
+                    return connection_error("request frame on stream 0");
+                }
+                // streams opened by clients are odd, those opened by servers even.
+                if (sid % 2 == 1) != self.server {
+                    return connection_error(&format!("invalid stream id {}", sid));
+                }
+                if is_active(sid) {
+                    return connection_error(&format!("stream {} is already active", sid));
+                }
+                if sid <= self.last_stream_id {
+                    return Err(Violation::Stream(
+                        sid,
+                        ERR_INVALID,
+                        format!("stream id {} has been used already", sid),
+                    ));
+                }
+                self.last_stream_id = sid;
+            }
+            Body::RequestN(_) | Body::Cancel() | Body::Payload(_) => {
+                if sid == 0 {
+                    return connection_error("stream frame on stream 0");
+                }
+                if !is_active(sid) {
+                    return Err(Violation::Ignore(format!("stream {} is not active", sid)));
+                }
+            }
+            Body::Error(_) => {
+                if sid != 0 && !is_active(sid) {
+                    return Err(Violation::Ignore(format!("stream {} is not active", sid)));
+                }
+            }
+            Body::Unknown(..) => unreachable!(),
+        }
+        Ok(())
+    }
+}
+
+fn connection_error(reason: &str) -> Result<(), Violation> {
+    Err(Violation::Connection(ERR_CONN_FAILED, reason.to_string()))
+}
+
+/// Flags defined for each frame type, the others are reserved.
+fn defined_flags(body: &Body) -> u16 {
+    let flags = match body {
+        Body::Setup(_) => Frame::FLAG_METADATA | Frame::FLAG_RESUME | Frame::FLAG_LEASE,
+        Body::Lease(_) | Body::MetadataPush(_) => Frame::FLAG_METADATA,
+        Body::Keepalive(_) => Frame::FLAG_RESPOND,
+        Body::RequestFNF(_) | Body::RequestResponse(_) | Body::RequestStream(_) => {
+            Frame::FLAG_METADATA | Frame::FLAG_FOLLOW
+        }
+        Body::RequestChannel(_) => Frame::FLAG_METADATA | Frame::FLAG_FOLLOW | Frame::FLAG_COMPLETE,
+        Body::Payload(_) => {
+            Frame::FLAG_METADATA | Frame::FLAG_FOLLOW | Frame::FLAG_COMPLETE | Frame::FLAG_NEXT
+        }
+        Body::RequestN(_) | Body::Cancel() | Body::Error(_) | Body::Resume(_) => 0,
+        Body::ResumeOK(_) | Body::Unknown(..) => 0,
+    };
+    flags | Frame::FLAG_IGNORE
+}
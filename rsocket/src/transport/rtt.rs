@@ -0,0 +1,71 @@
+use std::sync::Mutex;
+use std::time::Duration;
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use tokio::sync::watch;
+use tokio::time::Instant;
+
+/// Weight of the latest sample in the moving average, as TCP does for its smoothed RTT.
+const EWMA_ALPHA: f64 = 0.125;
+
+/// Round-trip times measured with the timestamps carried by KEEPALIVE frames.
+#[derive(Debug)]
+pub(crate) struct Rtt {
+    started: Instant,
+    ewma: Mutex<Option<Duration>>,
+    samples: watch::Sender<Option<Duration>>,
+    // keeps the channel open while nobody is watching
+    latest: watch::Receiver<Option<Duration>>,
+}
+
+impl Rtt {
+    pub(crate) fn new() -> Rtt {
+        let (samples, latest) = watch::channel(None);
+        Rtt {
+            started: Instant::now(),
+            ewma: Mutex::new(None),
+            samples,
+            latest,
+        }
+    }
+
+    /// Returns the data of an outgoing KEEPALIVE frame.
+    pub(crate) fn stamp(&self) -> Bytes {
+        let mut bf = BytesMut::with_capacity(8);
+        bf.put_u64(self.started.elapsed().as_micros() as u64);
+        bf.freeze()
+    }
+
+    /// Records a sample from the data echoed back by the peer.
+    pub(crate) fn on_echo(&self, data: Option<&Bytes>) {
+        let mut data = match data {
+            Some(it) if it.len() == 8 => it.clone(),
+            _ => return,
+        };
+        let sent = Duration::from_micros(data.get_u64());
+        let sample = match self.started.elapsed().checked_sub(sent) {
+            Some(it) => it,
+            None => return,
+        };
+        {
+            let mut ewma = self.ewma.lock().unwrap();
+            *ewma = Some(match *ewma {
+                Some(prev) => prev.mul_f64(1.0 - EWMA_ALPHA) + sample.mul_f64(EWMA_ALPHA),
+                None => sample,
+            });
+        }
+        let _ = self.samples.send(Some(sample));
+    }
+
+    pub(crate) fn latest(&self) -> Option<Duration> {
+        *self.latest.borrow()
+    }
+
+    pub(crate) fn ewma(&self) -> Option<Duration> {
+        *self.ewma.lock().unwrap()
+    }
+
+    pub(crate) fn watch(&self) -> watch::Receiver<Option<Duration>> {
+        self.latest.clone()
+    }
+}
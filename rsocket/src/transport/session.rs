@@ -0,0 +1,580 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bytes::Bytes;
+use dashmap::DashMap;
+use futures::{FutureExt, SinkExt, StreamExt};
+use tokio::sync::{mpsc, oneshot, Notify};
+use tokio::time::Instant;
+
+use super::misc::Liveness;
+use super::outbound::OutboundReceiver;
+use super::rtt::Rtt;
+use super::spi::{FrameSink, FrameStream};
+use crate::error::{self, RSocketError};
+use crate::frame::{self, Body, Frame};
+use crate::runtime;
+use crate::utils::Writeable;
+use crate::Result;
+
+/// A fresh connection handed over to the writer, along with the position to replay from.
+pub(crate) type Handover = (Box<FrameSink>, u64);
+
+pub(crate) type SharedResumeState = Arc<Mutex<ResumeState>>;
+
+/// Options of a resumable session.
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+/// use rsocket_rust::prelude::*;
+///
+/// let opts = ResumeOptions::new()
+///     .session_duration(Duration::from_secs(30))
+///     .retained_bytes(1 << 20);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ResumeOptions {
+    token: Option<Bytes>,
+    session_duration: Duration,
+    retained_bytes: usize,
+    reconnect_interval: Duration,
+}
+
+impl Default for ResumeOptions {
+    fn default() -> ResumeOptions {
+        ResumeOptions {
+            token: None,
+            session_duration: Duration::from_secs(120),
+            retained_bytes: 4 << 20,
+            reconnect_interval: Duration::from_secs(1),
+        }
+    }
+}
+
+impl ResumeOptions {
+    pub fn new() -> ResumeOptions {
+        ResumeOptions::default()
+    }
+
+    /// Sets the token identifying the session, a random one is generated by default.
+    pub fn token(mut self, token: impl Into<Bytes>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// Sets how long a broken session is kept waiting to be resumed.
+    pub fn session_duration(mut self, duration: Duration) -> Self {
+        self.session_duration = duration;
+        self
+    }
+
+    /// Sets the upper bound in bytes of the frames retained until the peer acknowledges them.
+    pub fn retained_bytes(mut self, n: usize) -> Self {
+        self.retained_bytes = n;
+        self
+    }
+
+    /// Sets the delay between two reconnection attempts of a client.
+    pub fn reconnect_interval(mut self, interval: Duration) -> Self {
+        self.reconnect_interval = interval;
+        self
+    }
+
+    pub fn get_token(&self) -> Option<&Bytes> {
+        self.token.as_ref()
+    }
+
+    pub fn get_session_duration(&self) -> Duration {
+        self.session_duration
+    }
+
+    pub fn get_retained_bytes(&self) -> usize {
+        self.retained_bytes
+    }
+
+    pub fn get_reconnect_interval(&self) -> Duration {
+        self.reconnect_interval
+    }
+}
+
+/// Implied positions of a resumable session, with the outbound frames not acknowledged yet.
+pub(crate) struct ResumeState {
+    retained: VecDeque<Frame>,
+    retained_bytes: usize,
+    capacity: usize,
+    first_available: u64,
+    sent: u64,
+    received: u64,
+}
+
+impl ResumeState {
+    pub(crate) fn new(capacity: usize) -> ResumeState {
+        ResumeState {
+            retained: VecDeque::new(),
+            retained_bytes: 0,
+            capacity,
+            first_available: 0,
+            sent: 0,
+            received: 0,
+        }
+    }
+
+    /// Only stream-level frames count towards positions and get replayed.
+    fn is_resumable(frame: &Frame) -> bool {
+        match frame.get_body_ref() {
+            Body::RequestFNF(_)
+            | Body::RequestResponse(_)
+            | Body::RequestStream(_)
+            | Body::RequestChannel(_)
+            | Body::RequestN(_)
+            | Body::Cancel()
+            | Body::Payload(_) => true,
+            Body::Error(_) => frame.get_stream_id() != 0,
+            _ => false,
+        }
+    }
+
+    pub(crate) fn on_sent(&mut self, frame: &Frame) {
+        if !Self::is_resumable(frame) {
+            return;
+        }
+        let n = frame.len();
+        self.sent += n as u64;
+        self.retained_bytes += n;
+        self.retained.push_back(frame.clone());
+        // evicted frames can no longer be replayed, resuming before them will be rejected.
+        while self.retained_bytes > self.capacity {
+            match self.retained.pop_front() {
+                Some(it) => self.evict(it.len()),
+                None => break,
+            }
+        }
+    }
+
+    pub(crate) fn on_received(&mut self, frame: &Frame) {
+        if Self::is_resumable(frame) {
+            self.received += frame.len() as u64;
+        }
+    }
+
+    /// Drops the retained frames which the peer has received.
+    pub(crate) fn release(&mut self, position: u64) {
+        while let Some(front) = self.retained.front() {
+            let n = front.len();
+            if self.first_available + n as u64 > position {
+                break;
+            }
+            self.retained.pop_front();
+            self.evict(n);
+        }
+    }
+
+    pub(crate) fn can_replay(&self, position: u64) -> bool {
+        position >= self.first_available && position <= self.sent
+    }
+
+    /// Returns the frames to send again to a peer which has received up to `position`.
+    pub(crate) fn replay(&mut self, position: u64) -> Vec<Frame> {
+        self.release(position);
+        self.retained.iter().cloned().collect()
+    }
+
+    pub(crate) fn received_position(&self) -> u64 {
+        self.received
+    }
+
+    pub(crate) fn first_available_position(&self) -> u64 {
+        self.first_available
+    }
+
+    #[inline]
+    fn evict(&mut self, n: usize) {
+        self.retained_bytes -= n;
+        self.first_available += n as u64;
+    }
+}
+
+/// Length of the generated resume tokens, in bytes.
+const TOKEN_LENGTH: usize = 16;
+
+/// Generates a resume token from the random source of the operating system, since holding it is
+/// all it takes to take over a session.
+pub(crate) fn generate_token() -> Bytes {
+    let mut token = [0u8; TOKEN_LENGTH];
+    getrandom::getrandom(&mut token).expect("no random source available");
+    Bytes::copy_from_slice(&token)
+}
+
+/// Frames written before flushing the connection, at most.
+const MAX_WRITE_BATCH: usize = 128;
+
+/// Writes outbound frames to the current connection.
+///
+/// Without resume state the loop ends along with the connection. Otherwise frames are retained
+/// while disconnected, then replayed onto the next connection handed over.
+///
+/// Frames ready at once are written together, flushing the connection after them.
+///
+/// Once `shutdown` fires, the frames queued so far are flushed and the connection gets closed.
+pub(crate) async fn write_loop(
+    mut frames: OutboundReceiver,
+    mut handovers: mpsc::UnboundedReceiver<Handover>,
+    mut shutdown: oneshot::Receiver<()>,
+    mut sink: Option<Box<FrameSink>>,
+    state: Option<SharedResumeState>,
+    keepalive: Option<(Duration, Arc<Rtt>)>,
+) {
+    let mut handovers_open = true;
+    let mut next_keepalive = keepalive.as_ref().map(|(it, _)| Instant::now() + *it);
+    loop {
+        let tick = next_keepalive.filter(|_| sink.is_some());
+        let frame = tokio::select! {
+            next = handovers.recv(), if handovers_open => {
+                match next {
+                    Some((next_sink, position)) => {
+                        let replay = match &state {
+                            Some(state) => state.lock().unwrap().replay(position),
+                            None => vec![],
+                        };
+                        // a connection superseded by the new one gets closed along with it.
+                        sink = Some(next_sink);
+                        for frame in replay {
+                            if !feed(&mut sink, frame).await {
+                                break;
+                            }
+                        }
+                        flush(&mut sink).await;
+                    }
+                    None => handovers_open = false,
+                }
+                None
+            }
+            next = frames.recv() => match next {
+                Some(frame) => Some(frame),
+                None => break,
+            },
+            _ = &mut shutdown => {
+                while let Some(Some(frame)) = frames.recv().now_or_never() {
+                    if !feed(&mut sink, frame).await {
+                        break;
+                    }
+                }
+                flush(&mut sink).await;
+                if let Some(mut it) = sink.take() {
+                    if let Err(e) = it.close().await {
+                        debug!("close connection failed: {}", e);
+                    }
+                }
+                break;
+            }
+            _ = tokio::time::sleep_until(tick.unwrap_or_else(Instant::now)), if tick.is_some() => {
+                next_keepalive = keepalive.as_ref().map(|(it, _)| Instant::now() + *it);
+                let rtt = keepalive.as_ref().map(|(_, it)| it.stamp()).unwrap_or_default();
+                Some(frame::Keepalive::builder(0, Frame::FLAG_RESPOND).set_data(rtt).build())
+            }
+        };
+        if let Some(frame) = frame {
+            let mut next = Some(frame);
+            let mut written = 0;
+            while let Some(mut frame) = next.take() {
+                if let Some(state) = &state {
+                    let mut state = state.lock().unwrap();
+                    if let Body::Keepalive(v) = &mut frame.body {
+                        v.set_last_received_position(state.received_position());
+                    }
+                    state.on_sent(&frame);
+                }
+                if !feed(&mut sink, frame).await {
+                    break;
+                }
+                written += 1;
+                if written < MAX_WRITE_BATCH {
+                    next = frames.recv().now_or_never().flatten();
+                }
+            }
+            flush(&mut sink).await;
+        }
+        if sink.is_none() && (state.is_none() || !handovers_open) {
+            break;
+        }
+    }
+}
+
+/// Buffers a frame to write, dropping the connection once it fails.
+async fn feed(sink: &mut Option<Box<FrameSink>>, frame: Frame) -> bool {
+    if let Some(it) = sink.as_mut() {
+        if let Err(e) = it.feed(frame).await {
+            error!("write frame failed: {}", e);
+            *sink = None;
+        }
+    }
+    sink.is_some()
+}
+
+/// Writes the frames buffered so far, dropping the connection once it fails.
+async fn flush(sink: &mut Option<Box<FrameSink>>) -> bool {
+    if let Some(it) = sink.as_mut() {
+        if let Err(e) = it.flush().await {
+            error!("write frame failed: {}", e);
+            *sink = None;
+        }
+    }
+    sink.is_some()
+}
+
+/// Frames read ahead of their dispatch at most, beyond which the connection stops reading.
+pub(crate) const READ_AHEAD: usize = 64;
+
+/// Forwards inbound frames of a connection until it breaks, waiting while `READ_AHEAD` of them
+/// are not dispatched yet.
+pub(crate) async fn read_loop(
+    mut stream: Box<FrameStream>,
+    read_tx: &mpsc::Sender<Frame>,
+    state: Option<&SharedResumeState>,
+    liveness: &Liveness,
+) {
+    while let Some(next) = stream.next().await {
+        let frame = match next {
+            Ok(frame) => frame,
+            Err(e) => {
+                error!("read frame failed: {}", e);
+                break;
+            }
+        };
+        liveness.touch();
+        if let Some(state) = state {
+            let mut state = state.lock().unwrap();
+            state.on_received(&frame);
+            if let Body::Keepalive(v) = frame.get_body_ref() {
+                state.release(v.get_last_received_position());
+            }
+        }
+        if let Err(e) = read_tx.send(frame).await {
+            error!("forward frame failed: {}", e);
+            break;
+        }
+    }
+}
+
+/// Asks the server to resume a session over a fresh connection.
+///
+/// Returns the position the server has received up to, which is where replaying starts.
+pub(crate) async fn resume_session(
+    sink: &mut Box<FrameSink>,
+    stream: &mut Box<FrameStream>,
+    token: &Bytes,
+    state: &SharedResumeState,
+) -> Result<u64> {
+    let sending = {
+        let state = state.lock().unwrap();
+        frame::Resume::builder(0, 0)
+            .set_token(token.clone())
+            .set_last_received_server_position(state.received_position())
+            .set_first_available_client_position(state.first_available_position())
+            .build()
+    };
+    sink.send(sending).await?;
+    let frame = match stream.next().await {
+        Some(next) => next?,
+        None => return Err(RSocketError::ConnectionClosed("resume".into()).into()),
+    };
+    match frame.get_body() {
+        Body::ResumeOK(v) => {
+            let position = v.get_position();
+            if state.lock().unwrap().can_replay(position) {
+                Ok(position)
+            } else {
+                let desc = format!("position {} is no longer available", position);
+                Err(RSocketError::RejectedResume(desc).into())
+            }
+        }
+        Body::Error(e) => Err(RSocketError::from_frame(&e).into()),
+        _ => Err(RSocketError::RejectedResume("unexpected frame".into()).into()),
+    }
+}
+
+pub(crate) async fn reject_resume(mut sink: Box<FrameSink>, reason: &str) {
+    let sending = frame::Error::builder(0, 0)
+        .set_code(error::ERR_REJECT_RESUME)
+        .set_data(Bytes::from(reason.to_owned()))
+        .build();
+    if let Err(e) = sink.send(sending).await {
+        error!("reject resume failed: {}", e);
+    }
+}
+
+/// A session accepted by the server, which survives its connection.
+pub(crate) struct ServerSession {
+    handovers: mpsc::UnboundedSender<Handover>,
+    /// Dropped once the session is removed, which ends the dispatch loop of its socket
+    read_tx: Mutex<Option<mpsc::Sender<Frame>>>,
+    state: SharedResumeState,
+    lifetime: Duration,
+    generation: AtomicU64,
+    resumed: Notify,
+}
+
+impl ServerSession {
+    pub(crate) fn state(&self) -> &SharedResumeState {
+        &self.state
+    }
+
+    fn reader(&self) -> Option<mpsc::Sender<Frame>> {
+        self.read_tx.lock().unwrap().clone()
+    }
+
+    /// Stops forwarding frames to the socket of the session, failing its pending requests.
+    fn close(&self) {
+        self.read_tx.lock().unwrap().take();
+    }
+}
+
+/// Resumable sessions of a server, keyed by resume token.
+pub(crate) struct SessionStore {
+    opts: ResumeOptions,
+    sessions: DashMap<Bytes, Arc<ServerSession>>,
+}
+
+impl SessionStore {
+    pub(crate) fn new(opts: ResumeOptions) -> SessionStore {
+        SessionStore {
+            opts,
+            sessions: DashMap::new(),
+        }
+    }
+
+    /// Registers a new session, returning the handovers its writer should listen to.
+    pub(crate) fn open(
+        &self,
+        token: Bytes,
+        read_tx: mpsc::Sender<Frame>,
+        lifetime: Duration,
+    ) -> (Arc<ServerSession>, mpsc::UnboundedReceiver<Handover>) {
+        let (handovers, handovers_rx) = mpsc::unbounded_channel();
+        let session = Arc::new(ServerSession {
+            handovers,
+            read_tx: Mutex::new(Some(read_tx)),
+            state: Arc::new(Mutex::new(ResumeState::new(self.opts.retained_bytes))),
+            lifetime,
+            generation: AtomicU64::new(0),
+            resumed: Notify::new(),
+        });
+        if let Some(previous) = self.sessions.insert(token, session.clone()) {
+            warn!("resume token reused, previous session dropped");
+            previous.close();
+        }
+        (session, handovers_rx)
+    }
+
+    /// Removes a session and closes it.
+    pub(crate) fn remove(&self, token: &Bytes, session: &Arc<ServerSession>) {
+        self.sessions
+            .remove_if(token, |_, v| Arc::ptr_eq(v, session));
+        session.close();
+    }
+
+    /// Reads frames of a session until its connection breaks, then keeps the session around
+    /// for the configured duration.
+    ///
+    /// `generation` counts the connections of the session, a connection stops being read once
+    /// another one resumes the session.
+    pub(crate) async fn serve(
+        self: Arc<Self>,
+        token: Bytes,
+        session: Arc<ServerSession>,
+        generation: u64,
+        stream: Box<FrameStream>,
+    ) {
+        let superseded = session.resumed.notified();
+        if session.generation.load(Ordering::SeqCst) != generation {
+            return;
+        }
+        let read_tx = match session.reader() {
+            Some(it) => it,
+            None => return,
+        };
+        let liveness = Liveness::new();
+        tokio::select! {
+            _ = read_loop(stream, &read_tx, Some(&session.state), &liveness) => {}
+            _ = liveness.expired(session.lifetime) => {
+                warn!("keepalive timeout, wait for the session to be resumed");
+            }
+            // superseded by a newer connection
+            _ = superseded => return,
+            // the session has been closed
+            _ = read_tx.closed() => return,
+        }
+        self.expire(token, session, generation);
+    }
+
+    /// Resumes a session over a fresh connection.
+    ///
+    /// A session whose connection is still alive gets taken over: the server may not have
+    /// noticed yet that the client lost it, as after a NAT rebinding.
+    pub(crate) async fn resume(
+        self: Arc<Self>,
+        resume: frame::Resume,
+        mut sink: Box<FrameSink>,
+        stream: Box<FrameStream>,
+    ) {
+        let found = resume.get_token().as_ref().and_then(|token| {
+            self.sessions
+                .get(token)
+                .map(|it| (token.clone(), it.value().clone()))
+        });
+        let (token, session) = match found {
+            Some(it) => it,
+            None => return reject_resume(sink, "no such session").await,
+        };
+        let position = resume.get_last_received_server_position();
+        let accepted = {
+            let state = session.state.lock().unwrap();
+            if state.can_replay(position)
+                && resume.get_first_available_client_position() <= state.received_position()
+            {
+                Some(state.received_position())
+            } else {
+                None
+            }
+        };
+        let received = match accepted {
+            Some(it) => it,
+            None => {
+                self.remove(&token, &session);
+                return reject_resume(sink, "position is no longer available").await;
+            }
+        };
+
+        // stops reading the previous connection, if any.
+        let generation = session.generation.fetch_add(1, Ordering::SeqCst) + 1;
+        session.resumed.notify_waiters();
+
+        let sending = frame::ResumeOK::builder(0, 0)
+            .set_position(received)
+            .build();
+        if let Err(e) = sink.send(sending).await {
+            error!("respond RESUME_OK failed: {}", e);
+            return self.expire(token, session, generation);
+        }
+        if session.handovers.send((sink, position)).is_err() {
+            // the session has been closed meanwhile.
+            return;
+        }
+        self.serve(token, session, generation, stream).await
+    }
+
+    /// Removes the session unless it gets resumed within the session duration.
+    fn expire(self: Arc<Self>, token: Bytes, session: Arc<ServerSession>, generation: u64) {
+        let duration = self.opts.session_duration;
+        runtime::spawn(async move {
+            tokio::time::sleep(duration).await;
+            if session.generation.load(Ordering::SeqCst) == generation {
+                debug!("session expired: {:?}", token);
+                self.remove(&token, &session);
+            }
+        });
+    }
+}
@@ -1,6 +1,8 @@
 use std::future::Future;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
 
 use async_stream::stream;
 use async_trait::async_trait;
@@ -8,27 +10,69 @@ use bytes::{Buf, BufMut, Bytes, BytesMut};
 use dashmap::{mapref::entry::Entry, DashMap};
 use futures::future::{AbortHandle, Abortable};
 use futures::{Sink, SinkExt, Stream, StreamExt};
-use tokio::sync::{mpsc, oneshot, RwLock};
-
-use super::fragmentation::{Joiner, Splitter};
-use super::misc::{debug_frame, Counter, StreamID};
+use tokio::io::AsyncRead;
+use tokio::sync::{mpsc, oneshot, Notify, RwLock};
+
+use super::context::{ConnectionScope, MimeTypes, RequestContext};
+use super::dispatch::{DispatchOptions, Executor};
+use super::fragmentation::{
+    read_chunk, BodySender, Joiner, PayloadBody, ReassemblyOptions, Splitter, BODY_CHUNK_SIZE,
+};
+use super::lease::LeaseTracker;
+use super::misc::{debug_frame, Counter, Credit, StreamID};
+use super::outbound::{Outbound, QueueDepth};
+use super::protocol::{Validator, Violation};
+use super::rtt::Rtt;
 use super::spi::*;
+use super::stream::{InteractionType, StreamInfo, Streams};
+use super::subscription::{Delivery, Inbound, StreamOptions, Subscription};
+use crate::core::ConnectionRegistry;
 use crate::error::{self, RSocketError};
+use crate::extension::{CompositeMetadata, DeadlineMetadata, MimeType};
 use crate::frame::{self, Body, Frame};
 use crate::payload::{Payload, SetupPayload};
-use crate::spi::{Flux, RSocket, ServerResponder};
+use crate::spi::{Acceptor, Flux, LeaseStrategy, RSocket};
 use crate::utils::EmptyRSocket;
 use crate::{runtime, Result};
 
+/// Delay before asking a lease strategy again after it granted nothing.
+const LEASE_RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often a closing connection checks whether its interactions have completed.
+const DRAIN_INTERVAL: Duration = Duration::from_millis(10);
+
+/// How long a closing connection waits for its last frames to be written, a detached session
+/// having no writer until it resumes.
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Source of the connection ids, unique within the process.
+static CONNECTION_ID: AtomicU64 = AtomicU64::new(1);
+
 struct DuplexSocketInner {
+    /// Identifies the connection within the process
+    id: u64,
     seq: StreamID,
     responder: Responder,
-    tx: mpsc::UnboundedSender<Frame>,
-    handlers: DashMap<u32, Handler>,
+    tx: Outbound,
+    handlers: Arc<DashMap<u32, Handler>>,
     splitter: Option<Splitter>,
     joiners: DashMap<u32, Joiner>,
+    /// Bodies of the requests whose data is streamed to their handler
+    bodies: DashMap<u32, BodySender>,
     /// AbortHandles for Response futures/streams
     abort_handles: Arc<DashMap<u32, AbortHandle>>,
+    /// Demand granted by the peer for streams we are producing
+    credits: Arc<DashMap<u32, Arc<Credit>>>,
+    /// Lease granted by the peer for the requests we send
+    requester_lease: LeaseTracker,
+    /// Lease granted to the peer for the requests we respond to
+    responder_lease: LeaseTracker,
+    /// Round-trip times measured with our KEEPALIVE frames
+    rtt: Arc<Rtt>,
+    /// Set once the connection stops accepting new requests
+    closing: AtomicBool,
+    /// Lifecycle of the streams in flight
+    streams: Streams,
 }
 
 #[derive(Clone)]
@@ -43,6 +87,22 @@ pub(crate) struct ServerRequester {
 
 pub(crate) struct DuplexSocket {
     inner: Arc<DuplexSocketInner>,
+    lease_strategy: Option<Arc<dyn LeaseStrategy>>,
+    /// Runs fire_and_forget and metadata_push handlers
+    executor: Executor,
+    validator: Validator,
+    /// MIME types of the connection, composite metadata may carry deadlines
+    mimes: Arc<MimeTypes>,
+    /// How long an async acceptor may take before the SETUP gets rejected
+    setup_timeout: Option<Duration>,
+    /// Peer of the connection, as known to the transport
+    info: Option<Arc<ConnectionInfo>>,
+    /// Registry the connection joins once set up, with the signal disconnecting it
+    registry: Option<(ConnectionRegistry, Arc<Notify>)>,
+    /// Bounds on the fragments held while reassembling them
+    reassembly: ReassemblyOptions,
+    /// Streamed bodies waiting for the handler of their request to be called
+    pending_bodies: DashMap<u32, PayloadBody>,
 }
 
 #[derive(Clone)]
@@ -53,27 +113,45 @@ struct Responder {
 #[derive(Debug)]
 enum Handler {
     ReqRR(oneshot::Sender<Result<Option<Payload>>>),
-    ReqRS(mpsc::Sender<Result<Payload>>),
-    ReqRC(mpsc::Sender<Result<Payload>>),
+    ReqRS(Inbound),
+    ReqRC(Inbound),
 }
 
 struct Cancel {}
 
+/// Cancels a request_response whose caller stopped waiting before the response arrived.
+struct PendingRequest<'a> {
+    inner: &'a DuplexSocketInner,
+    sid: u32,
+}
+
+impl Drop for PendingRequest<'_> {
+    fn drop(&mut self) {
+        if let Err(e) = self.inner.cancel_inbound(self.sid) {
+            debug!("cancel REQUEST_RESPONSE {} failed: {}", self.sid, e);
+        }
+    }
+}
+
 impl DuplexSocketInner {
-    fn new(
-        first_stream_id: u32,
-        tx: mpsc::UnboundedSender<Frame>,
-        splitter: Option<Splitter>,
-    ) -> Self {
+    fn new(first_stream_id: u32, tx: Outbound, splitter: Option<Splitter>) -> Self {
         let (canceller_tx, canceller_rx) = mpsc::channel::<u32>(32);
         let this = Self {
+            id: CONNECTION_ID.fetch_add(1, Ordering::Relaxed),
             seq: StreamID::from(first_stream_id),
             tx,
             responder: Responder::new(),
-            handlers: DashMap::new(),
+            handlers: Arc::new(DashMap::new()),
             joiners: DashMap::new(),
+            bodies: DashMap::new(),
             splitter,
             abort_handles: Arc::new(DashMap::new()),
+            credits: Arc::new(DashMap::new()),
+            requester_lease: LeaseTracker::default(),
+            responder_lease: LeaseTracker::default(),
+            rtt: Arc::new(Rtt::new()),
+            closing: AtomicBool::new(false),
+            streams: Streams::default(),
         };
         this
     }
@@ -82,16 +160,65 @@ impl DuplexSocketInner {
 impl DuplexSocket {
     pub(crate) fn new(
         first_stream_id: u32,
-        tx: mpsc::UnboundedSender<Frame>,
+        tx: Outbound,
         splitter: Option<Splitter>,
     ) -> DuplexSocket {
         DuplexSocket {
             inner: Arc::new(DuplexSocketInner::new(first_stream_id, tx, splitter)),
+            lease_strategy: None,
+            executor: Executor::new(DispatchOptions::default()),
+            // servers open even streams, clients odd ones.
+            validator: Validator::new(first_stream_id.is_multiple_of(2)),
+            mimes: Arc::new(MimeTypes::default()),
+            setup_timeout: None,
+            info: None,
+            registry: None,
+            reassembly: ReassemblyOptions::default(),
+            pending_bodies: DashMap::new(),
         }
     }
 
+    pub(crate) fn set_dispatch_options(&mut self, opts: DispatchOptions) {
+        self.executor = Executor::new(opts);
+    }
+
+    /// Bounds how long an async acceptor may take to accept the SETUP of the peer.
+    pub(crate) fn set_setup_timeout(&mut self, timeout: Option<Duration>) {
+        self.setup_timeout = timeout;
+    }
+
+    /// Sets what the transport knows about the peer, for acceptors and responders to see.
+    pub(crate) fn set_connection_info(&mut self, info: Option<ConnectionInfo>) {
+        self.info = info.map(Arc::new);
+    }
+
+    /// Registers the connection once set up, it gets closed when `disconnect` is notified.
+    pub(crate) fn set_registry(&mut self, registry: ConnectionRegistry, disconnect: Arc<Notify>) {
+        self.registry = Some((registry, disconnect));
+    }
+
+    pub(crate) fn set_reassembly_options(&mut self, opts: ReassemblyOptions) {
+        self.reassembly = opts;
+    }
+
+    /// Identifies the connection within the process.
+    pub(crate) fn connection_id(&self) -> u64 {
+        self.inner.id
+    }
+
+    /// Sets the strategy granting leases to peers which honor them.
+    pub(crate) fn set_lease_strategy(&mut self, strategy: Option<Arc<dyn LeaseStrategy>>) {
+        self.lease_strategy = strategy;
+    }
+
     pub(crate) async fn setup(&mut self, setup: SetupPayload) -> Result<()> {
-        let mut bu = frame::Setup::builder(0, 0);
+        self.mimes = Arc::new(MimeTypes::from(&setup));
+        let mut bu = if setup.honor_lease() {
+            self.inner.requester_lease.enable();
+            frame::Setup::builder(0, Frame::FLAG_LEASE)
+        } else {
+            frame::Setup::builder(0, 0)
+        };
         if let Some(s) = setup.data_mime_type() {
             bu = bu.set_mime_data(s);
         }
@@ -100,6 +227,9 @@ impl DuplexSocket {
         }
         bu = bu.set_keepalive(setup.keepalive_interval());
         bu = bu.set_lifetime(setup.keepalive_lifetime());
+        if let Some(token) = setup.resume_token() {
+            bu = bu.set_token(token.clone());
+        }
         let (d, m) = setup.split();
         if let Some(b) = d {
             bu = bu.set_data(b);
@@ -117,41 +247,77 @@ impl DuplexSocket {
 
     pub(crate) async fn dispatch(
         &mut self,
-        frame: Frame,
-        acceptor: Option<&ServerResponder>,
+        mut frame: Frame,
+        acceptor: Option<&Acceptor>,
     ) -> Result<()> {
-        if let Some(frame) = self.join_frame(frame).await {
-            self.process_once(frame, acceptor).await;
+        let inner = &self.inner;
+        if let Err(violation) = self.validator.check(&mut frame, |sid| inner.is_active(sid)) {
+            return self.on_violation(violation);
+        }
+        if let Some(frame) = self.join_frame(frame).await? {
+            // errors on stream 0 terminate the connection, except CONNECTION_CLOSE which lets
+            // the streams in flight complete before the peer closes it.
+            if let (0, Body::Error(e)) = (frame.get_stream_id(), frame.get_body_ref()) {
+                if e.get_code() == error::ERR_CONN_CLOSED {
+                    debug!("peer is closing the connection");
+                    self.inner.closing.store(true, Ordering::SeqCst);
+                    return Ok(());
+                }
+                return Err(RSocketError::from_frame(e).into());
+            }
+            let sid = frame.get_stream_id();
+            let processed = self.process_once(frame, acceptor).await;
+            if self.pending_bodies.remove(&sid).is_some() {
+                // the request has been rejected, the rest of its body is ignored.
+                self.inner.bodies.remove(&sid);
+            }
+            return processed;
         }
         Ok(())
     }
 
     #[inline]
-    async fn process_once(&mut self, msg: Frame, acceptor: Option<&ServerResponder>) {
+    async fn process_once(&mut self, msg: Frame, acceptor: Option<&Acceptor>) -> Result<()> {
         let sid = msg.get_stream_id();
         let flag = msg.get_flag();
         debug_frame(false, &msg);
+        if let Body::RequestFNF(_)
+        | Body::RequestResponse(_)
+        | Body::RequestStream(_)
+        | Body::RequestChannel(_) = msg.get_body_ref()
+        {
+            if self.reject_request(sid, &msg) {
+                return Ok(());
+            }
+        }
         match msg.get_body() {
             Body::Setup(v) => {
-                if let Err(e) = self
-                    .on_setup(acceptor, sid, flag, SetupPayload::from(v))
-                    .await
-                {
+                let mut setup = SetupPayload::from(v);
+                setup.set_honor_lease(flag & Frame::FLAG_LEASE != 0);
+                setup.set_connection_info(self.info.clone());
+                setup.set_connection_id(self.inner.id);
+                self.mimes = Arc::new(MimeTypes::from(&setup));
+                if let Err(e) = self.on_setup(acceptor, sid, flag, setup).await {
+                    let code = match e.downcast_ref::<RSocketError>() {
+                        Some(RSocketError::UnsupportedSetup(_)) => error::ERR_UNSUPPORTED_SETUP,
+                        _ => error::ERR_REJECT_SETUP,
+                    };
                     let errmsg = format!("{}", e);
                     let sending = frame::Error::builder(0, 0)
-                        .set_code(error::ERR_REJECT_SETUP)
+                        .set_code(code)
                         .set_data(Bytes::from(errmsg))
                         .build();
                     if self.inner.tx.send(sending).is_err() {
                         error!("Reject setup failed");
                     }
+                    // the frames held back while accepting are dropped along with the connection.
+                    return Err(e);
                 }
             }
-            Body::Resume(v) => {
-                // TODO: support resume
-            }
-            Body::ResumeOK(v) => {
-                // TODO: support resume ok
+            Body::Resume(_) | Body::ResumeOK(_) | Body::Unknown(..) => {
+                // resumption is negotiated by the connection before any frame gets dispatched,
+                // and unknown frames are rejected by the validator.
+                warn!("unexpected frame on established connection: sid={}", sid);
             }
             Body::MetadataPush(v) => {
                 let input = Payload::from(v);
@@ -166,12 +332,14 @@ impl DuplexSocket {
                 self.on_request_response(sid, flag, input).await;
             }
             Body::RequestStream(v) => {
+                let n = v.get_initial_request_n();
                 let input = Payload::from(v);
-                self.on_request_stream(sid, flag, input).await;
+                self.on_request_stream(sid, flag, n, input).await;
             }
             Body::RequestChannel(v) => {
+                let n = v.get_initial_request_n();
                 let input = Payload::from(v);
-                self.on_request_channel(sid, flag, input).await;
+                self.on_request_channel(sid, flag, n, input).await;
             }
             Body::Payload(v) => {
                 let input = Payload::from(v);
@@ -181,107 +349,253 @@ impl DuplexSocket {
                 if flag & Frame::FLAG_RESPOND != 0 {
                     debug!("got keepalive: {:?}", v);
                     self.on_keepalive(v).await;
+                } else {
+                    self.inner.rtt.on_echo(v.get_data());
                 }
             }
             Body::RequestN(v) => {
-                // TODO: support RequestN
+                self.on_request_n(sid, v.get_n());
             }
             Body::Error(v) => {
-                // TODO: support error
                 self.on_error(sid, flag, v).await;
             }
             Body::Cancel() => {
                 self.on_cancel(sid, flag).await;
             }
             Body::Lease(v) => {
-                // TODO: support Lease
+                let ttl = Duration::from_millis(v.get_ttl() as u64);
+                self.inner
+                    .requester_lease
+                    .grant(ttl, v.get_number_of_requests());
+            }
+        }
+        Ok(())
+    }
+
+    /// Rejects a request received while the connection is closing or without a valid lease.
+    #[inline]
+    fn reject_request(&self, sid: u32, msg: &Frame) -> bool {
+        let e = if self.inner.closing.load(Ordering::SeqCst) {
+            RSocketError::RequestRejected("connection is closing".into()).into()
+        } else {
+            match self.inner.responder_lease.try_acquire() {
+                Ok(()) => return false,
+                Err(e) => e,
+            }
+        };
+        if let Body::RequestFNF(_) = msg.get_body_ref() {
+            warn!("drop fire_and_forget {}: {}", sid, e);
+            return true;
+        }
+        let sending = frame::Error::builder(sid, 0)
+            .set_code(error::ERR_REJECTED)
+            .set_data(Bytes::from(e.to_string()))
+            .build();
+        if let Err(e) = self.inner.tx.send(sending) {
+            error!("reject request failed: {}", e);
+        }
+        true
+    }
+
+    /// Grants leases to the peer until the connection is gone.
+    fn start_leasing(&self, strategy: Arc<dyn LeaseStrategy>) {
+        self.inner.responder_lease.enable();
+        let inner = Arc::downgrade(&self.inner);
+        runtime::spawn(async move {
+            loop {
+                let inner = match inner.upgrade() {
+                    Some(it) => it,
+                    None => break,
+                };
+                let wait = match strategy.next_lease(inner.streams.len()) {
+                    Some(lease) if !lease.ttl.is_zero() => {
+                        inner
+                            .responder_lease
+                            .grant(lease.ttl, lease.number_of_requests);
+                        let mut bu = frame::Lease::builder(0, 0)
+                            .set_ttl(lease.ttl.as_millis() as u32)
+                            .set_number_of_requests(lease.number_of_requests);
+                        if let Some(m) = lease.metadata {
+                            bu = bu.set_metadata(m);
+                        }
+                        if let Err(e) = inner.tx.send(bu.build()) {
+                            error!("send LEASE failed: {}", e);
+                            break;
+                        }
+                        lease.ttl
+                    }
+                    _ => LEASE_RETRY_INTERVAL,
+                };
+                drop(inner);
+                tokio::time::sleep(wait).await;
             }
+        });
+    }
+
+    fn on_violation(&self, violation: Violation) -> Result<()> {
+        let (sid, code, reason) = match violation {
+            Violation::Ignore(reason) => {
+                debug!("ignore frame: {}", reason);
+                return Ok(());
+            }
+            Violation::Stream(sid, code, reason) => (sid, code, reason),
+            Violation::Connection(code, reason) => (0, code, reason),
+        };
+        warn!("invalid frame on stream {}: {}", sid, reason);
+        let sending = frame::Error::builder(sid, 0)
+            .set_code(code)
+            .set_data(Bytes::from(reason.clone()))
+            .build();
+        if let Err(e) = self.inner.tx.send(sending) {
+            error!("send ERROR failed: {}", e);
+        }
+        if sid == 0 {
+            return Err(RSocketError::must_new_from_code(code, reason).into());
         }
+        Ok(())
     }
 
     #[inline]
-    async fn join_frame(&self, input: Frame) -> Option<Frame> {
+    async fn join_frame(&self, input: Frame) -> Result<Option<Frame>> {
         let (is_follow, is_payload) = input.is_followable_or_payload();
         if !is_follow {
-            return Some(input);
+            return Ok(Some(input));
         }
         let sid = input.get_stream_id();
+        if is_payload {
+            let body = self.inner.bodies.get(&sid).map(|it| it.clone());
+            if let Some(body) = body {
+                self.stream_body(sid, &body, input).await;
+                return Ok(None);
+            }
+        }
         if input.get_flag() & Frame::FLAG_FOLLOW != 0 {
-            // TODO: check conflict
-            self.inner
-                .joiners
-                .entry(sid)
-                .or_insert_with(Joiner::new)
-                .push(input);
-            return None;
+            let limit = self.reassembly.get_max_partial_streams();
+            if self.inner.joiners.len() >= limit && !self.inner.joiners.contains_key(&sid) {
+                let reason = format!("more than {} fragmented streams", limit);
+                let violation = Violation::Connection(error::ERR_CONN_FAILED, reason);
+                return self.on_violation(violation).map(|_| None);
+            }
+            // only PAYLOAD frames continue a joiner: a request frame for its stream has been
+            // rejected by the validator, since a stream being joined counts as active.
+            let size = {
+                let mut joiner = self.inner.joiners.entry(sid).or_insert_with(Joiner::new);
+                joiner.push(input);
+                joiner.len()
+            };
+            if size > self.reassembly.get_max_payload_size() {
+                self.on_oversized(sid).await;
+                return Ok(None);
+            }
+            if self.reassembly.is_streaming_bodies() {
+                return Ok(self.open_body(sid, false).await);
+            }
+            return Ok(None);
         }
 
         if !is_payload {
-            return Some(input);
+            return Ok(Some(input));
         }
 
         match self.inner.joiners.remove(&sid) {
-            None => Some(input),
+            None => Ok(Some(input)),
             Some((_, mut joiner)) => {
                 joiner.push(input);
-                let flag = joiner.get_flag();
-                let first = joiner.first();
-                match &first.body {
-                    frame::Body::RequestResponse(_) => {
-                        let pa: Payload = joiner.into();
-                        let result = frame::RequestResponse::builder(sid, flag)
-                            .set_all(pa.split())
-                            .build();
-                        Some(result)
-                    }
-                    frame::Body::RequestStream(b) => {
-                        let n = b.get_initial_request_n();
-                        let pa: Payload = joiner.into();
-                        let result = frame::RequestStream::builder(sid, flag)
-                            .set_initial_request_n(n)
-                            .set_all(pa.split())
-                            .build();
-                        Some(result)
-                    }
-                    frame::Body::RequestFNF(_) => {
-                        let pa: Payload = joiner.into();
-                        let result = frame::RequestFNF::builder(sid, flag)
-                            .set_all(pa.split())
-                            .build();
-                        Some(result)
-                    }
-                    frame::Body::RequestChannel(b) => {
-                        let n = b.get_initial_request_n();
-                        let pa: Payload = joiner.into();
-                        let result = frame::RequestChannel::builder(sid, flag)
-                            .set_initial_request_n(n)
-                            .set_all(pa.split())
-                            .build();
-                        Some(result)
-                    }
-                    frame::Body::Payload(b) => {
-                        let pa: Payload = joiner.into();
-                        let result = frame::Payload::builder(sid, flag)
-                            .set_all(pa.split())
-                            .build();
-                        Some(result)
-                    }
-                    _ => unreachable!(),
+                if joiner.len() > self.reassembly.get_max_payload_size() {
+                    self.on_oversized(sid).await;
+                    return Ok(None);
+                }
+                if self.reassembly.is_streaming_bodies() && joiner.has_body() {
+                    // the last fragment completes the metadata, and the body along with it.
+                    self.inner.joiners.insert(sid, joiner);
+                    return Ok(self.open_body(sid, true).await);
                 }
+                let flag = joiner.get_flag();
+                let first = joiner.first().get_body_ref().clone();
+                Ok(Some(rebuild(sid, flag, &first, joiner.into())))
             }
         }
     }
 
+    /// Hands a fragmented request over to its handler once its metadata is complete, its data
+    /// following as a `PayloadBody`.
+    async fn open_body(&self, sid: u32, last: bool) -> Option<Frame> {
+        // fragments of payloads and of other requests are reassembled.
+        let (_, joiner) = self.inner.joiners.remove_if(&sid, |_, it| {
+            it.has_body() && (last || it.has_complete_metadata())
+        })?;
+        let flag = joiner.get_flag();
+        let first = joiner.first().get_body_ref().clone();
+        let joined: Payload = joiner.into();
+        let (data, metadata) = joined.split();
+        let (tx, body) = PayloadBody::channel(self.reassembly.get_max_payload_size());
+        if let Some(chunk) = data {
+            // the fragments joined so far are within the limit already.
+            tx.deliver(chunk).await;
+        }
+        if !last {
+            self.inner.bodies.insert(sid, tx);
+        }
+        self.pending_bodies.insert(sid, body);
+        Some(rebuild(sid, flag, &first, Payload::new(None, metadata)))
+    }
+
+    /// Passes the data of a fragment on to the body it belongs to, no more frames being read
+    /// until the body has room for it.
+    async fn stream_body(&self, sid: u32, body: &BodySender, input: Frame) {
+        let last = input.get_flag() & Frame::FLAG_FOLLOW == 0;
+        let chunk = match input.get_body() {
+            Body::Payload(it) => it.split().0,
+            _ => None,
+        };
+        if let Some(chunk) = chunk {
+            body.deliver(chunk).await;
+        }
+        if last {
+            self.inner.bodies.remove(&sid);
+        }
+    }
+
     #[inline]
-    async fn on_error(&mut self, sid: u32, flag: u16, input: frame::Error) {
+    fn on_request_n(&self, sid: u32, n: u32) {
+        match self.inner.credits.get(&sid) {
+            Some(credit) => credit.add(n),
+            None => debug!("ignore REQUEST_N for inactive stream: sid={}", sid),
+        }
+    }
+
+    /// Cancels a stream whose fragments add up to more than the reassembly allows.
+    async fn on_oversized(&self, sid: u32) {
+        let max = self.reassembly.get_max_payload_size();
+        warn!("payload of stream {} exceeds {} bytes, cancel it", sid, max);
         self.inner.joiners.remove(&sid);
-        // pick handler
-        if let Some((_, handler)) = self.inner.handlers.remove(&sid) {
-            let desc = input
-                .get_data_utf8()
-                .map(|it| it.to_string())
-                .unwrap_or_default();
-            let e = RSocketError::must_new_from_code(input.get_code(), desc);
+        let channel = self.inner.streams.interaction(sid) == Some(InteractionType::RequestChannel);
+        let sending = if self.inner.streams.is_requester(sid) && !channel {
+            frame::Cancel::builder(sid, 0).build()
+        } else {
+            frame::Error::builder(sid, 0)
+                .set_code(error::ERR_CANCELED)
+                .set_data(Bytes::from(format!("payload exceeds {} bytes", max)))
+                .build()
+        };
+        if let Err(e) = self.inner.tx.send(sending) {
+            error!("cancel stream {} failed: {}", sid, e);
+        }
+        let e = RSocketError::RequestCancelled(format!("payload exceeds {} bytes", max));
+        self.fail_stream(sid, e);
+    }
+
+    #[inline]
+    async fn on_error(&mut self, sid: u32, flag: u16, input: frame::Error) {
+        // an ERROR terminates both directions of the stream.
+        self.fail_stream(sid, RSocketError::from_frame(&input));
+    }
+
+    /// Terminates a stream, failing its handler with the given error.
+    fn fail_stream(&self, sid: u32, e: RSocketError) {
+        let handler = self.inner.handlers.remove(&sid);
+        self.inner.terminate(sid);
+        if let Some((_, handler)) = handler {
             match handler {
                 Handler::ReqRR(tx) => {
                     if tx.send(Err(e.into())).is_err() {
@@ -289,12 +603,12 @@ impl DuplexSocket {
                     }
                 }
                 Handler::ReqRS(tx) => {
-                    if (tx.send(Err(e.into())).await).is_err() {
+                    if !tx.send(Err(e.into())) {
                         error!("respond with error for REQUEST_STREAM failed!");
                     };
                 }
                 Handler::ReqRC(tx) => {
-                    if (tx.send(Err(e.into())).await).is_err() {
+                    if !tx.send(Err(e.into())) {
                         error!("respond with error for REQUEST_CHANNEL failed!");
                     }
                 }
@@ -304,11 +618,16 @@ impl DuplexSocket {
 
     #[inline]
     async fn on_cancel(&mut self, sid: u32, _flag: u16) {
-        if let Some((sid, abort_handle)) = self.inner.abort_handles.remove(&sid) {
-            abort_handle.abort();
+        if self.inner.streams.interaction(sid) == Some(InteractionType::RequestChannel) {
+            // the peer wants no more of our payloads, while its own side stays open.
+            debug!("outbound of REQUEST_CHANNEL {} cancelled", sid);
+            self.inner.streams.cancel(sid);
+            self.inner.close_local(sid);
+            return;
         }
-        self.inner.joiners.remove(&sid);
-        if let Some((_, handler)) = self.inner.handlers.remove(&sid) {
+        let handler = self.inner.handlers.remove(&sid);
+        self.inner.terminate(sid);
+        if let Some((_, handler)) = handler {
             let e: Result<_> =
                 Err(RSocketError::RequestCancelled("request has been cancelled".into()).into());
             match handler {
@@ -330,60 +649,101 @@ impl DuplexSocket {
 
     #[inline]
     async fn on_payload(&mut self, sid: u32, flag: u16, input: Payload) {
-        match self.inner.handlers.entry(sid) {
-            Entry::Occupied(o) => {
-                match o.get() {
-                    Handler::ReqRR(_) => match o.remove() {
-                        Handler::ReqRR(sender) => {
-                            if flag & Frame::FLAG_NEXT != 0 {
-                                if sender.send(Ok(Some(input))).is_err() {
-                                    error!("response successful payload for REQUEST_RESPONSE failed: sid={}",sid);
-                                }
-                            } else if sender.send(Ok(None)).is_err() {
-                                error!("response successful payload for REQUEST_RESPONSE failed: sid={}",sid);
-                            }
-                        }
-                        _ => unreachable!(),
-                    },
-                    Handler::ReqRS(sender) => {
-                        if flag & Frame::FLAG_NEXT != 0 {
-                            if sender.is_closed() {
-                                self.send_cancel_frame(sid);
-                            } else if let Err(e) = sender.send(Ok(input)).await {
-                                error!(
-                                    "response successful payload for REQUEST_STREAM failed: sid={}",
-                                    sid
-                                );
-                                self.send_cancel_frame(sid);
-                            }
-                        }
-                        if flag & Frame::FLAG_COMPLETE != 0 {
-                            o.remove();
-                        }
-                    }
-                    Handler::ReqRC(sender) => {
-                        // TODO: support channel
-                        if flag & Frame::FLAG_NEXT != 0 {
-                            if sender.is_closed() {
-                                self.send_cancel_frame(sid);
-                            } else if (sender.clone().send(Ok(input)).await).is_err() {
-                                error!("response successful payload for REQUEST_CHANNEL failed: sid={}",sid);
-                                self.send_cancel_frame(sid);
-                            }
-                        }
-                        if flag & Frame::FLAG_COMPLETE != 0 {
-                            o.remove();
-                        }
+        // no lock on the handlers is held while the stream gets closed.
+        let sender = match self.inner.handlers.get(&sid) {
+            Some(handler) => match handler.value() {
+                Handler::ReqRR(_) => None,
+                Handler::ReqRS(tx) | Handler::ReqRC(tx) => Some(tx.clone()),
+            },
+            None => {
+                warn!("invalid payload id {}: no such request!", sid);
+                return;
+            }
+        };
+        self.inner.streams.activate(sid);
+        let sender = match sender {
+            Some(it) => it,
+            None => {
+                let handler = self.inner.handlers.remove(&sid);
+                // release the stream before the caller gets woken up.
+                self.inner.close_remote(sid);
+                if let Some((_, Handler::ReqRR(sender))) = handler {
+                    let result = if flag & Frame::FLAG_NEXT != 0 {
+                        Some(input)
+                    } else {
+                        None
+                    };
+                    if sender.send(Ok(result)).is_err() {
+                        error!(
+                            "response successful payload for REQUEST_RESPONSE failed: sid={}",
+                            sid
+                        );
                     }
                 }
+                return;
+            }
+        };
+        if flag & Frame::FLAG_NEXT != 0 {
+            match sender.deliver(input) {
+                Delivery::Delivered => (),
+                Delivery::Unrequested => {
+                    warn!("stream {} sent more payloads than requested", sid);
+                    let e = RSocketError::RequestCancelled("more payloads than requested".into());
+                    sender.send(Err(e.into()));
+                    self.send_cancel_frame(sid);
+                    self.inner.close_remote(sid);
+                    return;
+                }
+                Delivery::Closed => {
+                    // the subscriber went away without cancelling, stop the peer from sending more.
+                    debug!("subscriber of stream {} is gone", sid);
+                    self.send_cancel_frame(sid);
+                    self.inner.close_remote(sid);
+                    return;
+                }
+            }
+        }
+        if flag & Frame::FLAG_COMPLETE != 0 {
+            self.inner.close_remote(sid);
+        }
+    }
+
+    /// Fails every pending request and stops every stream in flight once the connection is gone.
+    pub(crate) fn fail_pending(&self) {
+        self.inner.closing.store(true, Ordering::SeqCst);
+        self.inner.abort_handles.retain(|_, it| {
+            it.abort();
+            false
+        });
+        self.inner.credits.clear();
+        self.inner.joiners.clear();
+        self.inner.bodies.retain(|_, body| {
+            let e = RSocketError::ConnectionClosed("connection has been closed".into());
+            body.fail(e.into());
+            false
+        });
+        self.inner.streams.cancel_all();
+        self.inner.streams.clear();
+        let sids: Vec<u32> = self.inner.handlers.iter().map(|it| *it.key()).collect();
+        for sid in sids {
+            let handler = match self.inner.handlers.remove(&sid) {
+                Some((_, it)) => it,
+                None => continue,
+            };
+            let e = RSocketError::ConnectionClosed("connection has been closed".into());
+            let failed = match handler {
+                Handler::ReqRR(tx) => tx.send(Err(e.into())).is_err(),
+                Handler::ReqRS(tx) | Handler::ReqRC(tx) => !tx.send(Err(e.into())),
+            };
+            if failed {
+                debug!("notify connection closed failed: sid={}", sid);
             }
-            Entry::Vacant(_) => warn!("invalid payload id {}: no such request!", sid),
         }
     }
 
     #[inline]
     fn send_cancel_frame(&self, sid: u32) {
-        let cancel_frame = frame::Cancel::builder(sid, Frame::FLAG_COMPLETE).build();
+        let cancel_frame = frame::Cancel::builder(sid, 0).build();
         if let Err(e) = self.inner.tx.send(cancel_frame) {
             error!("Sending CANCEL frame failed: sid={}, reason: {}", sid, e);
         }
@@ -396,33 +756,75 @@ impl DuplexSocket {
     #[inline]
     async fn on_setup(
         &self,
-        acceptor: Option<&ServerResponder>,
+        acceptor: Option<&Acceptor>,
         sid: u32,
         flag: u16,
         setup: SetupPayload,
     ) -> Result<()> {
-        match acceptor {
-            None => {
-                self.inner.responder.set(Box::new(EmptyRSocket)).await;
-                Ok(())
+        let leasing = if setup.honor_lease() {
+            match &self.lease_strategy {
+                Some(it) => Some(it.clone()),
+                None => {
+                    let desc = "lease is not supported".to_owned();
+                    return Err(RSocketError::UnsupportedSetup(desc).into());
+                }
             }
-            Some(gen) => match gen(setup, Box::new(self.server_requester())) {
-                Ok(it) => {
-                    self.inner.responder.set(it).await;
-                    Ok(())
+        } else {
+            None
+        };
+        // registered before the acceptor runs, for it to tag the connection.
+        if let Some((registry, disconnect)) = &self.registry {
+            let requester = Box::new(self.server_requester());
+            registry.register(self.inner.id, setup.clone(), requester, disconnect.clone());
+        }
+        let accepted = match acceptor {
+            None => Ok(Box::new(EmptyRSocket) as Box<dyn RSocket>),
+            Some(Acceptor::Sync(gen)) => gen(setup, Box::new(self.server_requester())),
+            // no frame gets dispatched until the acceptor resolves.
+            Some(Acceptor::Async(gen)) => {
+                let accepting = gen(setup, Box::new(self.server_requester()));
+                match self.setup_timeout {
+                    Some(timeout) => match tokio::time::timeout(timeout, accepting).await {
+                        Ok(it) => it,
+                        Err(_) => {
+                            let desc = format!("setup not accepted within {:?}", timeout);
+                            Err(RSocketError::RejectedSetup(desc).into())
+                        }
+                    },
+                    None => accepting.await,
                 }
-                Err(e) => Err(e),
-            },
+            }
+        };
+        let accepted = match accepted {
+            Ok(it) => {
+                self.inner.responder.set(it).await;
+                Ok(())
+            }
+            Err(e) => Err(e),
+        };
+        if accepted.is_ok() {
+            if let Some(strategy) = leasing {
+                self.start_leasing(strategy);
+            }
         }
+        accepted
     }
 
     #[inline]
     async fn on_fire_and_forget(&mut self, sid: u32, input: Payload) {
-        // TODO: Spawning a task here in case responer call goes  pending, which would hold
-        // up the entire dispatch loop
-        if let Err(e) = self.inner.responder.fire_and_forget(input).await {
-            error!("respond fire_and_forget failed: {:?}", e);
-        }
+        let responder = self.inner.responder.clone();
+        let ctx = self.context(
+            sid,
+            InteractionType::FireAndForget,
+            self.deadline_of(&input),
+        );
+        self.executor
+            .execute(ctx.scope(async move {
+                if let Err(e) = responder.fire_and_forget(input).await {
+                    error!("respond fire_and_forget failed: {:?}", e);
+                }
+            }))
+            .await;
     }
 
     #[inline]
@@ -430,20 +832,21 @@ impl DuplexSocket {
         let responder = self.inner.responder.clone();
 
         let mut tx = self.inner.tx.clone();
-        let splitter = self.inner.splitter.clone();    
-        let (abort_handle, abort_registration) = AbortHandle::new_pair();       
-        let abort_handles = self.inner.abort_handles.clone();
+        let splitter = self.inner.splitter.clone();
+        let (abort_handle, abort_registration) = AbortHandle::new_pair();
+        let ctx = self.accept_stream(sid, InteractionType::RequestResponse, &input);
+        self.inner.abort_handles.insert(sid, abort_handle);
+        let inner = Arc::downgrade(&self.inner);
         runtime::spawn(async move {
-                       
-            abort_handles.insert(sid, abort_handle);
-            let result= Abortable::new(responder.request_response(input), abort_registration).await;
-            abort_handles.remove(&sid);
+            let handler = ctx.scope(responder.request_response(input));
+            let result = Abortable::new(handler, abort_registration).await;
 
             // Abort for futures adds an extra result wrapper, so unwrap that and continue
             let Ok(result) = result else {
                 // cancelled
                 return;
             };
+            tx.ready().await;
 
             match result {
                 Ok(Some(res)) => {
@@ -460,117 +863,241 @@ impl DuplexSocket {
                     DuplexSocketInner::try_send_complete(&mut tx, sid, Frame::FLAG_COMPLETE).await;
                 }
                 Err(e) => {
-                    let sending = frame::Error::builder(sid, 0)
-                        .set_code(error::ERR_APPLICATION)
-                        .set_data(Bytes::from(e.to_string()))
-                        .build();
+                    let sending = error_frame(sid, &e);
                     if let Err(e) = tx.send(sending) {
                         error!("respond REQUEST_RESPONSE failed: {}", e);
                     }
                 }
             };
+            if let Some(inner) = inner.upgrade() {
+                inner.release(sid);
+            }
         });
     }
 
     #[inline]
-    async fn on_request_stream(&self, sid: u32, flag: u16, input: Payload) {
+    async fn on_request_stream(&self, sid: u32, flag: u16, n: u32, input: Payload) {
         let responder = self.inner.responder.clone();
         let mut tx = self.inner.tx.clone();
         let splitter = self.inner.splitter.clone();
-        let abort_handles = self.inner.abort_handles.clone();
-        runtime::spawn(async move {
-            let (abort_handle, abort_registration) = AbortHandle::new_pair();
-            abort_handles.insert(sid, abort_handle);
-            let mut payloads = Abortable::new(responder.request_stream(input), abort_registration);
-            while let Some(next) = payloads.next().await {
-                match next {
-                    Ok(it) => {
-                        DuplexSocketInner::try_send_payload(
-                            &splitter,
-                            &mut tx,
-                            sid,
-                            it,
-                            Frame::FLAG_NEXT,
-                        )
-                        .await;
-                    }
-                    Err(e) => {
-                        let sending = frame::Error::builder(sid, 0)
-                            .set_code(error::ERR_APPLICATION)
-                            .set_data(Bytes::from(format!("{}", e)))
-                            .build();
-                        tx.send(sending).expect("Send stream response failed");
+        let credit = Arc::new(Credit::new(n));
+        let (abort_handle, abort_registration) = AbortHandle::new_pair();
+        let ctx = self.accept_stream(sid, InteractionType::RequestStream, &input);
+        self.inner.credits.insert(sid, credit.clone());
+        self.inner.abort_handles.insert(sid, abort_handle);
+        let inner = Arc::downgrade(&self.inner);
+        // the stream is polled within the context too.
+        runtime::spawn(ctx.scope(async move {
+            let mut payloads = responder.request_stream(input);
+            let task = async {
+                loop {
+                    // poll the responder only when the requester has asked for more, and
+                    // there is room in the outbound queue
+                    credit.acquire().await;
+                    tx.ready().await;
+                    match payloads.next().await {
+                        Some(Ok(it)) => {
+                            DuplexSocketInner::try_send_payload(
+                                &splitter,
+                                &mut tx,
+                                sid,
+                                it,
+                                Frame::FLAG_NEXT,
+                            )
+                            .await;
+                        }
+                        Some(Err(e)) => {
+                            let sending = error_frame(sid, &e);
+                            if let Err(e) = tx.send(sending) {
+                                error!("respond REQUEST_STREAM failed: {}", e);
+                            }
+                            return false;
+                        }
+                        None => return true,
                     }
-                };
+                }
+            };
+            let completed = match Abortable::new(task, abort_registration).await {
+                Ok(it) => it,
+                // cancelled, the stream has been released already.
+                Err(_) => return,
+            };
+            if completed {
+                let complete = frame::Payload::builder(sid, Frame::FLAG_COMPLETE).build();
+                if let Err(e) = tx.send(complete) {
+                    error!("complete REQUEST_STREAM failed: {}", e);
+                }
             }
-            abort_handles.remove(&sid);
-            let complete = frame::Payload::builder(sid, Frame::FLAG_COMPLETE).build();
-            tx.send(complete)
-                .expect("Send stream complete response failed");
-        });
+            if let Some(inner) = inner.upgrade() {
+                inner.release(sid);
+            }
+        }));
     }
 
     #[inline]
-    async fn on_request_channel(&self, sid: u32, flag: u16, first: Payload) {
+    async fn on_request_channel(&self, sid: u32, flag: u16, n: u32, first: Payload) {
         let responder = self.inner.responder.clone();
-        let tx = self.inner.tx.clone();
-        let (sender, mut receiver) = mpsc::channel::<Result<Payload>>(32);
-        sender.send(Ok(first)).await.expect("Send failed!");
+        let mut tx = self.inner.tx.clone();
+        let splitter = self.inner.splitter.clone();
+        let opts = StreamOptions::default();
+        // the first payload comes along with REQUEST_CHANNEL, the rest gets requested below.
+        let (sender, receiver) = Inbound::channel(0);
+        let ctx = self.accept_stream(sid, InteractionType::RequestChannel, &first);
+        if !sender.send(Ok(first)) {
+            error!("deliver first payload of REQUEST_CHANNEL {} failed", sid);
+        }
         self.register_handler(sid, Handler::ReqRC(sender));
-        let abort_handles = self.inner.abort_handles.clone();
-        runtime::spawn(async move {
-            // respond client channel
-            let outputs = responder.request_channel(Box::pin(stream! {
-                while let Some(it) = receiver.recv().await{
-                    yield it;
+        let credit = Arc::new(Credit::new(n));
+        let (abort_handle, abort_registration) = AbortHandle::new_pair();
+        self.inner.credits.insert(sid, credit.clone());
+        self.inner.abort_handles.insert(sid, abort_handle);
+        // the requester may send a single payload, completing its side right away.
+        let completed = flag & Frame::FLAG_COMPLETE != 0;
+        if completed {
+            self.inner.close_remote(sid);
+        }
+        let inner = Arc::downgrade(&self.inner);
+        runtime::spawn(ctx.scope(async move {
+            // the first payload came along with REQUEST_CHANNEL, ask for the rest.
+            let closing = inner.clone();
+            let inputs = Subscription::new(sid, tx.clone(), receiver, &opts).on_cancel(Box::new(
+                move || match closing.upgrade() {
+                    Some(inner) => inner.cancel_inbound(sid),
+                    None => Ok(()),
+                },
+            ));
+            if !completed {
+                if let Err(e) = inputs.request(opts.get_initial_request_n() - 1) {
+                    error!("respond REQUEST_N failed: {}", e);
                 }
-            }));
-            let (abort_handle, abort_registration) = AbortHandle::new_pair();
-            abort_handles.insert(sid, abort_handle);
-            let mut outputs = Abortable::new(outputs, abort_registration);
-
-            // TODO: support custom RequestN.
-            let request_n = frame::RequestN::builder(sid, 0).build();
-
-            if let Err(e) = tx.send(request_n) {
-                error!("respond REQUEST_N failed: {}", e);
             }
 
-            while let Some(next) = outputs.next().await {
-                let sending = match next {
-                    Ok(payload) => {
-                        let (data, metadata) = payload.split();
-                        let mut bu = frame::Payload::builder(sid, Frame::FLAG_NEXT);
-                        if let Some(b) = data {
-                            bu = bu.set_data(b);
+            // respond client channel
+            let mut outputs = responder.request_channel(Box::pin(inputs));
+
+            let task = async {
+                loop {
+                    credit.acquire().await;
+                    tx.ready().await;
+                    match outputs.next().await {
+                        Some(Ok(payload)) => {
+                            DuplexSocketInner::try_send_payload(
+                                &splitter,
+                                &mut tx,
+                                sid,
+                                payload,
+                                Frame::FLAG_NEXT,
+                            )
+                            .await
                         }
-                        if let Some(b) = metadata {
-                            bu = bu.set_metadata(b);
+                        Some(Err(e)) => {
+                            let sending = error_frame(sid, &e);
+                            if let Err(e) = tx.send(sending) {
+                                error!("respond REQUEST_CHANNEL failed: {}", e);
+                            }
+                            return false;
                         }
-                        bu.build()
+                        None => return true,
                     }
-                    Err(e) => frame::Error::builder(sid, 0)
-                        .set_code(error::ERR_APPLICATION)
-                        .set_data(Bytes::from(format!("{}", e)))
-                        .build(),
-                };
-                tx.send(sending).expect("Send failed!");
+                }
+            };
+            let completed = match Abortable::new(task, abort_registration).await {
+                Ok(it) => it,
+                // cancelled, the stream has been released already.
+                Err(_) => return,
+            };
+            if let Some(inner) = inner.upgrade() {
+                inner.finish_outbound(sid, completed);
             }
-            abort_handles.remove(&sid);
-            let complete = frame::Payload::builder(sid, Frame::FLAG_COMPLETE).build();
-            if let Err(e) = tx.send(complete) {
-                error!("complete REQUEST_CHANNEL failed: {}", e);
+        }));
+    }
+
+    /// Opens a stream requested by the peer, returning the context its handler runs in.
+    fn accept_stream(
+        &self,
+        sid: u32,
+        interaction: InteractionType,
+        input: &Payload,
+    ) -> RequestContext {
+        self.inner.streams.open(sid, interaction, false);
+        let budget = self.deadline_of(input);
+        if let Some(budget) = budget {
+            self.expire_after(sid, budget);
+        }
+        self.context(sid, interaction, budget)
+    }
+
+    fn context(
+        &self,
+        sid: u32,
+        interaction: InteractionType,
+        budget: Option<Duration>,
+    ) -> RequestContext {
+        let connection = ConnectionScope {
+            id: self.inner.id,
+            mimes: self.mimes.clone(),
+            info: self.info.clone(),
+            outbound: self.inner.tx.clone(),
+        };
+        let ctx = RequestContext::new(
+            sid,
+            interaction,
+            connection,
+            budget.map(|it| Instant::now() + it),
+            self.inner.streams.cancellation(sid),
+        );
+        match self.pending_bodies.remove(&sid) {
+            Some((_, body)) => ctx.with_body(body),
+            None => ctx,
+        }
+    }
+
+    /// Deadline propagated by the requester within the composite metadata of a request.
+    fn deadline_of(&self, input: &Payload) -> Option<Duration> {
+        let composite = self.mimes.metadata.as_deref()
+            == MimeType::MESSAGE_X_RSOCKET_COMPOSITE_METADATA_V0.as_str();
+        if !composite {
+            return None;
+        }
+        input
+            .metadata()
+            .and_then(|it| CompositeMetadata::decode(&mut BytesMut::from(&it[..])).ok())
+            .and_then(|it| DeadlineMetadata::find(&it))
+            .map(|it| it.get_budget())
+    }
+
+    /// Aborts a request once the deadline propagated by the requester has elapsed.
+    fn expire_after(&self, sid: u32, budget: Duration) {
+        let inner = Arc::downgrade(&self.inner);
+        runtime::spawn(async move {
+            tokio::time::sleep(budget).await;
+            let inner = match inner.upgrade() {
+                Some(it) if it.streams.contains(sid) => it,
+                _ => return,
+            };
+            debug!("deadline of stream {} exceeded after {:?}", sid, budget);
+            inner.terminate(sid);
+            let sending = frame::Error::builder(sid, 0)
+                .set_code(error::ERR_CANCELED)
+                .set_data(Bytes::from("deadline exceeded"))
+                .build();
+            if let Err(e) = inner.tx.send(sending) {
+                error!("send ERROR failed: {}", e);
             }
         });
     }
 
     #[inline]
     async fn on_metadata_push(&mut self, input: Payload) {
-        // TODO: Spawning a task here in case responer call goes  pending, which would hold
-        // up the entire dispatch loop
-        if let Err(e) = self.inner.responder.metadata_push(input).await {
-            error!("response metadata_push failed: {:?}", e);
-        }
+        let responder = self.inner.responder.clone();
+        let ctx = self.context(0, InteractionType::MetadataPush, None);
+        self.executor
+            .execute(ctx.scope(async move {
+                if let Err(e) = responder.metadata_push(input).await {
+                    error!("response metadata_push failed: {:?}", e);
+                }
+            }))
+            .await;
     }
 
     #[inline]
@@ -585,6 +1112,16 @@ impl DuplexSocket {
         }
     }
 
+    pub(crate) fn rtt(&self) -> Arc<Rtt> {
+        self.inner.rtt.clone()
+    }
+
+    /// Closes the connection gracefully, see `DuplexSocketInner::dispose`.
+    pub(crate) fn dispose(&self, timeout: Duration) -> impl Future<Output = ()> + Send + 'static {
+        let inner = self.inner.clone();
+        async move { inner.dispose(timeout).await }
+    }
+
     pub(crate) fn client_requester(&self) -> ClientRequester {
         ClientRequester {
             inner: self.inner.clone(),
@@ -600,11 +1137,161 @@ impl DuplexSocket {
 
 // These are the immplementation functions for the requesters below
 impl DuplexSocketInner {
+    /// Checks whether a new request may be sent.
+    fn admit(&self) -> Result<()> {
+        if self.closing.load(Ordering::SeqCst) {
+            return Err(RSocketError::ConnectionClosed("connection is closing".into()).into());
+        }
+        self.requester_lease.try_acquire()
+    }
+
+    fn is_active(&self, sid: u32) -> bool {
+        // fragments of a request arrive before its stream gets opened.
+        self.streams.contains(sid)
+            || self.joiners.contains_key(&sid)
+            || self.bodies.contains_key(&sid)
+    }
+
+    fn is_alive(inner: &Weak<DuplexSocketInner>, sid: u32) -> bool {
+        inner.upgrade().is_some_and(|it| it.streams.contains(sid))
+    }
+
+    fn is_idle(&self) -> bool {
+        self.streams.is_empty()
+    }
+
+    /// Terminates a stream, dropping everything kept for it and stopping its producer.
+    fn release(&self, sid: u32) {
+        self.streams.remove(sid);
+        self.handlers.remove(&sid);
+        self.joiners.remove(&sid);
+        if let Some((_, body)) = self.bodies.remove(&sid) {
+            let e = RSocketError::RequestCancelled("stream terminated before its body".into());
+            body.fail(e.into());
+        }
+        self.credits.remove(&sid);
+        self.tx.forget(sid);
+        if let Some((_, it)) = self.abort_handles.remove(&sid) {
+            it.abort();
+        }
+    }
+
+    /// Terminates a stream abnormally, signalling the cancellation to its handler.
+    fn terminate(&self, sid: u32) {
+        self.streams.cancel(sid);
+        self.release(sid);
+    }
+
+    /// Completes the inbound side of a stream, releasing it once the outbound one is done too.
+    fn close_remote(&self, sid: u32) {
+        self.handlers.remove(&sid);
+        self.joiners.remove(&sid);
+        if self.streams.close(sid, false) {
+            self.release(sid);
+        }
+    }
+
+    /// Completes the outbound side of a stream, stopping its producer if it is still running.
+    ///
+    /// The stream gets released once the inbound side is done too.
+    fn close_local(&self, sid: u32) {
+        if let Some((_, it)) = self.abort_handles.remove(&sid) {
+            it.abort();
+        }
+        self.credits.remove(&sid);
+        if self.streams.close(sid, true) {
+            self.release(sid);
+        }
+    }
+
+    /// Ends the outbound side of a channel once its producer has stopped.
+    ///
+    /// A producer which completed sends COMPLETE and leaves the inbound side open, one which
+    /// failed has sent an ERROR already, which terminates the whole stream.
+    fn finish_outbound(&self, sid: u32, completed: bool) {
+        if !completed {
+            self.release(sid);
+            return;
+        }
+        let sending = frame::Payload::builder(sid, Frame::FLAG_COMPLETE).build();
+        if let Err(e) = self.tx.send(sending) {
+            error!("complete REQUEST_CHANNEL failed: {}", e);
+        }
+        self.close_local(sid);
+    }
+
+    /// Cancels the inbound side of a stream once its subscriber has gone.
+    fn cancel_inbound(&self, sid: u32) -> Result<()> {
+        if !self.streams.contains(sid) {
+            return Ok(());
+        }
+        self.close_remote(sid);
+        let sending = frame::Cancel::builder(sid, 0).build();
+        self.tx
+            .send(sending)
+            .map_err(|_| RSocketError::ConnectionClosed("closed".into()).into())
+    }
+
+    /// Cancels a channel we requested, together with the payloads we are still sending.
+    ///
+    /// While our side is open, the channel is terminated with an ERROR so that the responder
+    /// does not wait for payloads which will never come.
+    fn cancel_channel(&self, sid: u32) -> Result<()> {
+        if !self.abort_handles.contains_key(&sid) {
+            return self.cancel_inbound(sid);
+        }
+        self.release(sid);
+        let sending = frame::Error::builder(sid, 0)
+            .set_code(error::ERR_CANCELED)
+            .set_data(Bytes::from("request channel cancelled"))
+            .build();
+        self.tx
+            .send(sending)
+            .map_err(|_| RSocketError::ConnectionClosed("closed".into()).into())
+    }
+
+    fn active_streams(&self) -> Vec<StreamInfo> {
+        self.streams.snapshot()
+    }
+
+    /// Stops accepting new requests and waits up to `timeout` for the interactions in flight to
+    /// complete, then tells the peer the connection is being closed.
+    async fn dispose(&self, timeout: Duration) {
+        self.closing.store(true, Ordering::SeqCst);
+        let drained = tokio::time::timeout(timeout, async {
+            while !self.is_idle() {
+                tokio::time::sleep(DRAIN_INTERVAL).await;
+            }
+        })
+        .await;
+        if drained.is_err() {
+            debug!(
+                "interactions still in flight after {:?}, cancel them",
+                timeout
+            );
+        }
+        let sending = frame::Error::builder(0, 0)
+            .set_code(error::ERR_CONN_CLOSED)
+            .set_data(Bytes::from("connection closed"))
+            .build();
+        if let Err(e) = self.tx.send(sending) {
+            debug!("send CONNECTION_CLOSE failed: {}", e);
+            return;
+        }
+        let flushed = tokio::time::timeout(FLUSH_TIMEOUT, self.tx.flushed()).await;
+        if flushed.is_err() {
+            debug!("CONNECTION_CLOSE not written after {:?}", FLUSH_TIMEOUT);
+        }
+    }
+
     async fn metadata_push(&self, req: Payload) -> Result<()> {
-        let sid = self.seq.next();
+        if self.closing.load(Ordering::SeqCst) {
+            return Err(RSocketError::ConnectionClosed("connection is closing".into()).into());
+        }
+        self.tx.ready().await;
         let tx = self.tx.clone();
         let (_d, m) = req.split();
-        let mut bu = frame::MetadataPush::builder(sid, 0);
+        let mut bu = frame::MetadataPush::builder(0, 0);
         if let Some(b) = m {
             bu = bu.set_metadata(b);
         }
@@ -613,6 +1300,8 @@ impl DuplexSocketInner {
     }
 
     async fn fire_and_forget(&self, req: Payload) -> Result<()> {
+        self.admit()?;
+        self.tx.ready().await;
         let sid = self.seq.next();
         let tx = self.tx.clone();
         let splitter = self.splitter.clone();
@@ -665,16 +1354,30 @@ impl DuplexSocketInner {
         Ok(())
     }
 
-    async fn request_response(&self, req: Payload) -> Result<Option<Payload>> {
+    async fn request_response(
+        self: &Arc<Self>,
+        req: Payload,
+        timeout: Option<Duration>,
+    ) -> Result<Option<Payload>> {
+        self.admit()?;
         let (tx, rx) = oneshot::channel::<Result<Option<Payload>>>();
         let sid = self.seq.next();
         let sender = self.tx.clone();
         let splitter = self.splitter.clone();
 
         // Register handler
+        self.streams
+            .open(sid, InteractionType::RequestResponse, true);
         self.handlers.insert(sid, Handler::ReqRR(tx));
+        let pending = PendingRequest { inner: self, sid };
 
+        let inner = Arc::downgrade(self);
         runtime::spawn(async move {
+            sender.ready().await;
+            if !DuplexSocketInner::is_alive(&inner, sid) {
+                // cancelled before the request could be sent.
+                return;
+            }
             match splitter {
                 Some(sp) => {
                     let mut cuts: usize = 0;
@@ -730,20 +1433,186 @@ impl DuplexSocketInner {
                 }
             }
         });
-        match rx.await {
+        Self::wait_response(rx, timeout, pending).await
+    }
+
+    /// Request-Response whose data is read from `body`, sent in fragments as it is read.
+    async fn request_response_from<R>(
+        self: &Arc<Self>,
+        metadata: Option<Bytes>,
+        body: R,
+        timeout: Option<Duration>,
+    ) -> Result<Option<Payload>>
+    where
+        R: AsyncRead + Unpin + Send + 'static,
+    {
+        self.admit()?;
+        let (tx, rx) = oneshot::channel::<Result<Option<Payload>>>();
+        let sid = self.seq.next();
+        let sender = self.tx.clone();
+        let splitter = self.splitter.clone();
+
+        self.streams
+            .open(sid, InteractionType::RequestResponse, true);
+        self.handlers.insert(sid, Handler::ReqRR(tx));
+        let pending = PendingRequest { inner: self, sid };
+
+        let inner = Arc::downgrade(self);
+        runtime::spawn(async move {
+            let alive = || DuplexSocketInner::is_alive(&inner, sid);
+            let interaction = InteractionType::RequestResponse;
+            let sent =
+                Self::send_body(&sender, &splitter, sid, interaction, metadata, body, alive).await;
+            let (e, inner) = match (sent, inner.upgrade()) {
+                (Err(e), Some(inner)) => (e, inner),
+                _ => return,
+            };
+            // the request fails along with its body, the responder stops waiting for the rest.
+            if let Some((_, Handler::ReqRR(tx))) = inner.handlers.remove(&sid) {
+                let _ = tx.send(Err(e));
+            }
+            if let Err(e) = inner.cancel_inbound(sid) {
+                debug!("cancel REQUEST_RESPONSE {} failed: {}", sid, e);
+            }
+        });
+        Self::wait_response(rx, timeout, pending).await
+    }
+
+    /// Waits for the response of a request, which gets cancelled once `pending` is dropped.
+    async fn wait_response(
+        rx: oneshot::Receiver<Result<Option<Payload>>>,
+        timeout: Option<Duration>,
+        pending: PendingRequest<'_>,
+    ) -> Result<Option<Payload>> {
+        // dropping the future from here on cancels the request.
+        let result = match timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, rx).await {
+                Ok(it) => it,
+                Err(_) => return Err(RSocketError::Timeout(timeout).into()),
+            },
+            None => rx.await,
+        };
+        drop(pending);
+        match result {
             Ok(v) => v,
             Err(_e) => Err(RSocketError::WithDescription("request_response failed".into()).into()),
         }
     }
 
-    fn request_stream(&self, input: Payload) -> Flux<Result<Payload>> {
+    /// Fire-and-Forget whose data is read from `body`, sent in fragments as it is read.
+    async fn fire_and_forget_from<R>(&self, metadata: Option<Bytes>, body: R) -> Result<()>
+    where
+        R: AsyncRead + Unpin,
+    {
+        self.admit()?;
+        let sid = self.seq.next();
+        let interaction = InteractionType::FireAndForget;
+        let (tx, splitter) = (&self.tx, &self.splitter);
+        let sent = Self::send_body(tx, splitter, sid, interaction, metadata, body, || true).await;
+        if sent.is_err() {
+            // the responder drops the fragments received so far.
+            let _ = self.tx.send(frame::Cancel::builder(sid, 0).build());
+        }
+        sent
+    }
+
+    /// Sends the metadata of a request, then its data cut into fragments as it is read.
+    ///
+    /// Fragments are as large as the mtu allows, or `BODY_CHUNK_SIZE` without one, and are sent
+    /// as long as `alive` holds.
+    async fn send_body<R>(
+        tx: &Outbound,
+        splitter: &Option<Splitter>,
+        sid: u32,
+        interaction: InteractionType,
+        metadata: Option<Bytes>,
+        mut body: R,
+        alive: impl Fn() -> bool,
+    ) -> Result<()>
+    where
+        R: AsyncRead + Unpin,
+    {
+        let size = splitter
+            .as_ref()
+            .map_or(BODY_CHUNK_SIZE, |it| it.mtu() - frame::LEN_HEADER);
+        let mut pieces = match (splitter, metadata) {
+            (Some(sp), Some(m)) => sp.cut(Payload::new(None, Some(m)), 0).collect(),
+            (None, Some(m)) => vec![Payload::new(None, Some(m))],
+            (_, None) => vec![],
+        }
+        .into_iter();
+        let mut buf = BytesMut::new();
+        let mut first = true;
+        let mut prev: Option<Payload> = None;
+        loop {
+            let next = match pieces.next() {
+                Some(it) => it,
+                None => match read_chunk(&mut body, &mut buf, size).await? {
+                    chunk if chunk.is_empty() => break,
+                    chunk => Payload::new(Some(chunk), None),
+                },
+            };
+            if let Some(cur) = prev.replace(next) {
+                Self::send_fragment(tx, sid, interaction, first, cur, true).await?;
+                first = false;
+                if !alive() {
+                    return Ok(());
+                }
+            }
+        }
+        let last = prev.unwrap_or_else(|| Payload::new(None, None));
+        Self::send_fragment(tx, sid, interaction, first, last, false).await
+    }
+
+    async fn send_fragment(
+        tx: &Outbound,
+        sid: u32,
+        interaction: InteractionType,
+        first: bool,
+        fragment: Payload,
+        follow: bool,
+    ) -> Result<()> {
+        let flag = if follow { Frame::FLAG_FOLLOW } else { 0 };
+        let sending = match interaction {
+            _ if !first => frame::Payload::builder(sid, flag)
+                .set_all(fragment.split())
+                .build(),
+            InteractionType::RequestResponse => frame::RequestResponse::builder(sid, flag)
+                .set_all(fragment.split())
+                .build(),
+            _ => frame::RequestFNF::builder(sid, flag)
+                .set_all(fragment.split())
+                .build(),
+        };
+        tx.ready().await;
+        tx.send(sending)?;
+        Ok(())
+    }
+
+    fn request_stream(self: &Arc<Self>, input: Payload) -> Flux<Result<Payload>> {
+        Box::pin(self.request_stream_with(input, StreamOptions::default()))
+    }
+
+    fn request_stream_with(self: &Arc<Self>, input: Payload, opts: StreamOptions) -> Subscription {
+        if let Err(e) = self.admit() {
+            return Subscription::failed(self.tx.clone(), e);
+        }
         let sid = self.seq.next();
         let tx = self.tx.clone();
+        tx.set_weight(sid, opts.get_weight());
         // register handler
-        let (sender, mut receiver) = mpsc::channel::<Result<Payload>>(32);
+        let n = opts.get_initial_request_n();
+        let (sender, receiver) = Inbound::channel(n);
+        self.streams.open(sid, InteractionType::RequestStream, true);
         self.handlers.insert(sid, Handler::ReqRS(sender));
         let splitter = self.splitter.clone();
+        let inner = Arc::downgrade(self);
         runtime::spawn(async move {
+            tx.ready().await;
+            if !DuplexSocketInner::is_alive(&inner, sid) {
+                // cancelled before the request could be sent.
+                return;
+            }
             match splitter {
                 Some(sp) => {
                     let mut cuts: usize = 0;
@@ -754,6 +1623,7 @@ impl DuplexSocketInner {
                             let sending: Frame = if cuts == 1 {
                                 // make first frame as request_stream.
                                 frame::RequestStream::builder(sid, Frame::FLAG_FOLLOW)
+                                    .set_initial_request_n(n)
                                     .set_all(cur.split())
                                     .build()
                             } else {
@@ -773,9 +1643,12 @@ impl DuplexSocketInner {
                     }
 
                     let sending = if cuts == 0 {
-                        frame::RequestStream::builder(sid, 0).build()
+                        frame::RequestStream::builder(sid, 0)
+                            .set_initial_request_n(n)
+                            .build()
                     } else if cuts == 1 {
                         frame::RequestStream::builder(sid, 0)
+                            .set_initial_request_n(n)
                             .set_all(prev.unwrap().split())
                             .build()
                     } else {
@@ -790,6 +1663,7 @@ impl DuplexSocketInner {
                 }
                 None => {
                     let sending = frame::RequestStream::builder(sid, 0)
+                        .set_initial_request_n(n)
                         .set_all(input.split())
                         .build();
                     if let Err(e) = tx.send(sending) {
@@ -798,64 +1672,123 @@ impl DuplexSocketInner {
                 }
             }
         });
-        Box::pin(stream! {
-            while let Some(it) = receiver.recv().await{
-                yield it;
-            }
-        })
+        let inner = Arc::downgrade(self);
+        Subscription::new(sid, self.tx.clone(), receiver, &opts)
+            .on_cancel(Box::new(move || match inner.upgrade() {
+                Some(inner) => inner.cancel_inbound(sid),
+                None => Ok(()),
+            }))
+            .with_timeout(opts.get_timeout())
     }
 
-    fn request_channel(&self, mut reqs: Flux<Result<Payload>>) -> Flux<Result<Payload>> {
+    fn request_channel_with(
+        self: &Arc<Self>,
+        mut reqs: Flux<Result<Payload>>,
+        opts: StreamOptions,
+    ) -> Subscription {
+        if let Err(e) = self.admit() {
+            return Subscription::failed(self.tx.clone(), e);
+        }
         let sid = self.seq.next();
         let mut tx = self.tx.clone();
+        tx.set_weight(sid, opts.get_weight());
+        let n = opts.get_initial_request_n();
 
-        let (sender, mut receiver) = mpsc::channel::<Result<Payload>>(32);
+        let (sender, receiver) = Inbound::channel(n);
         // register handler
+        self.streams
+            .open(sid, InteractionType::RequestChannel, true);
         self.handlers.insert(sid, Handler::ReqRC(sender));
+        // the responder grants demand for our outbound payloads with REQUEST_N.
+        let credit = Arc::new(Credit::new(0));
+        self.credits.insert(sid, credit.clone());
+        let (abort_handle, abort_registration) = AbortHandle::new_pair();
+        self.abort_handles.insert(sid, abort_handle);
+        let inner = Arc::downgrade(self);
         let splitter = self.splitter.clone();
         runtime::spawn(async move {
-            let mut first = true;
-            while let Some(next) = reqs.next().await {
-                match next {
-                    Ok(it) => {
-                        if first {
-                            first = false;
-                            Self::try_send_channel(&splitter, &mut tx, sid, it, Frame::FLAG_NEXT)
+            // set when there was nothing to send at all, the request completes our side then.
+            let mut empty = false;
+            let task = async {
+                let mut first = true;
+                loop {
+                    // the first payload is carried by REQUEST_CHANNEL and needs no demand.
+                    if !first {
+                        credit.acquire().await;
+                    }
+                    tx.ready().await;
+                    match reqs.next().await {
+                        Some(Ok(it)) => {
+                            if first {
+                                first = false;
+                                Self::try_send_channel(
+                                    &splitter,
+                                    &mut tx,
+                                    sid,
+                                    it,
+                                    n,
+                                    Frame::FLAG_NEXT,
+                                )
                                 .await
-                        } else {
-                            Self::try_send_payload(&splitter, &mut tx, sid, it, Frame::FLAG_NEXT)
+                            } else {
+                                Self::try_send_payload(
+                                    &splitter,
+                                    &mut tx,
+                                    sid,
+                                    it,
+                                    Frame::FLAG_NEXT,
+                                )
                                 .await
+                            }
                         }
-                    }
-                    Err(e) => {
-                        let sending = frame::Error::builder(sid, 0)
-                            .set_code(error::ERR_APPLICATION)
-                            .set_data(Bytes::from(format!("{}", e)))
-                            .build();
-                        if let Err(e) = tx.send(sending) {
-                            error!("send REQUEST_CHANNEL failed: {}", e);
+                        Some(Err(e)) => {
+                            let sending = error_frame(sid, &e);
+                            if let Err(e) = tx.send(sending) {
+                                error!("send REQUEST_CHANNEL failed: {}", e);
+                            }
+                            return false;
                         }
-                    }
-                };
-            }
-            let sending = frame::Payload::builder(sid, Frame::FLAG_COMPLETE).build();
-            if let Err(e) = tx.send(sending) {
-                error!("complete REQUEST_CHANNEL failed: {}", e);
+                        None if first => {
+                            let sending = frame::RequestChannel::builder(sid, Frame::FLAG_COMPLETE)
+                                .set_initial_request_n(n)
+                                .build();
+                            if let Err(e) = tx.send(sending) {
+                                error!("send REQUEST_CHANNEL failed: {}", e);
+                            }
+                            empty = true;
+                            return true;
+                        }
+                        None => return true,
+                    };
+                }
+            };
+            let completed = match Abortable::new(task, abort_registration).await {
+                Ok(it) => it,
+                // cancelled, the stream has been released already.
+                Err(_) => return,
+            };
+            match inner.upgrade() {
+                Some(inner) if empty => inner.close_local(sid),
+                Some(inner) => inner.finish_outbound(sid, completed),
+                None => (),
             }
         });
-        Box::pin(stream! {
-            while let Some(it) = receiver.recv().await{
-                yield it;
-            }
-        })
+        let inner = Arc::downgrade(self);
+        Subscription::new(sid, self.tx.clone(), receiver, &opts)
+            .on_cancel(Box::new(move || match inner.upgrade() {
+                Some(inner) => inner.cancel_channel(sid),
+                None => Ok(()),
+            }))
+            .with_timeout(opts.get_timeout())
     }
 
     #[inline]
     async fn try_send_channel(
         splitter: &Option<Splitter>,
-        tx: &mut mpsc::UnboundedSender<Frame>,
+        tx: &mut Outbound,
         sid: u32,
         res: Payload,
+        n: u32,
         flag: u16,
     ) {
         // TODO
@@ -867,6 +1800,7 @@ impl DuplexSocketInner {
                     if let Some(cur) = prev.take() {
                         let sending = if cuts == 1 {
                             frame::RequestChannel::builder(sid, flag | Frame::FLAG_FOLLOW)
+                                .set_initial_request_n(n)
                                 .set_all(cur.split())
                                 .build()
                         } else {
@@ -885,9 +1819,12 @@ impl DuplexSocketInner {
                 }
 
                 let sending = if cuts == 0 {
-                    frame::RequestChannel::builder(sid, flag).build()
+                    frame::RequestChannel::builder(sid, flag)
+                        .set_initial_request_n(n)
+                        .build()
                 } else if cuts == 1 {
                     frame::RequestChannel::builder(sid, flag)
+                        .set_initial_request_n(n)
                         .set_all(prev.unwrap().split())
                         .build()
                 } else {
@@ -902,6 +1839,7 @@ impl DuplexSocketInner {
             }
             None => {
                 let sending = frame::RequestChannel::builder(sid, flag)
+                    .set_initial_request_n(n)
                     .set_all(res.split())
                     .build();
                 if let Err(e) = tx.send(sending) {
@@ -912,7 +1850,7 @@ impl DuplexSocketInner {
     }
 
     #[inline]
-    async fn try_send_complete(tx: &mut mpsc::UnboundedSender<Frame>, sid: u32, flag: u16) {
+    async fn try_send_complete(tx: &mut Outbound, sid: u32, flag: u16) {
         let sending = frame::Payload::builder(sid, flag).build();
         if let Err(e) = tx.send(sending) {
             error!("respond failed: {}", e);
@@ -922,7 +1860,7 @@ impl DuplexSocketInner {
     #[inline]
     async fn try_send_payload(
         splitter: &Option<Splitter>,
-        tx: &mut mpsc::UnboundedSender<Frame>,
+        tx: &mut Outbound,
         sid: u32,
         res: Payload,
         flag: u16,
@@ -1038,6 +1976,69 @@ impl RSocket for Responder {
     }
 }
 
+impl ClientRequester {
+    pub(crate) async fn request_response_with(
+        &self,
+        req: Payload,
+        timeout: Option<Duration>,
+    ) -> Result<Option<Payload>> {
+        self.inner.request_response(req, timeout).await
+    }
+
+    pub(crate) async fn request_response_from<R>(
+        &self,
+        metadata: Option<Bytes>,
+        body: R,
+        timeout: Option<Duration>,
+    ) -> Result<Option<Payload>>
+    where
+        R: AsyncRead + Unpin + Send + 'static,
+    {
+        self.inner
+            .request_response_from(metadata, body, timeout)
+            .await
+    }
+
+    pub(crate) async fn fire_and_forget_from<R>(
+        &self,
+        metadata: Option<Bytes>,
+        body: R,
+    ) -> Result<()>
+    where
+        R: AsyncRead + Unpin,
+    {
+        self.inner.fire_and_forget_from(metadata, body).await
+    }
+
+    pub(crate) fn request_stream_with(&self, req: Payload, opts: StreamOptions) -> Subscription {
+        self.inner.request_stream_with(req, opts)
+    }
+
+    pub(crate) fn request_channel_with(
+        &self,
+        reqs: Flux<Result<Payload>>,
+        opts: StreamOptions,
+    ) -> Subscription {
+        self.inner.request_channel_with(reqs, opts)
+    }
+
+    pub(crate) fn rtt(&self) -> &Rtt {
+        &self.inner.rtt
+    }
+
+    pub(crate) async fn dispose(&self, timeout: Duration) {
+        self.inner.dispose(timeout).await
+    }
+
+    pub(crate) fn queue_depth(&self) -> QueueDepth {
+        self.inner.tx.depth()
+    }
+
+    pub(crate) fn active_streams(&self) -> Vec<StreamInfo> {
+        self.inner.active_streams()
+    }
+}
+
 #[async_trait]
 impl RSocket for ClientRequester {
     /// Metadata-Push interaction model of RSocket.
@@ -1050,7 +2051,7 @@ impl RSocket for ClientRequester {
     }
     /// Request-Response interaction model of RSocket.
     async fn request_response(&self, req: Payload) -> Result<Option<Payload>> {
-        self.inner.request_response(req).await
+        self.inner.request_response(req, None).await
     }
     /// Request-Stream interaction model of RSocket.
     fn request_stream(&self, req: Payload) -> Flux<Result<Payload>> {
@@ -1058,7 +2059,10 @@ impl RSocket for ClientRequester {
     }
     /// Request-Channel interaction model of RSocket.
     fn request_channel(&self, reqs: Flux<Result<Payload>>) -> Flux<Result<Payload>> {
-        self.inner.request_channel(reqs)
+        Box::pin(
+            self.inner
+                .request_channel_with(reqs, StreamOptions::default()),
+        )
     }
 }
 
@@ -1083,7 +2087,7 @@ impl RSocket for ServerRequester {
     /// Request-Response interaction model of RSocket.
     async fn request_response(&self, req: Payload) -> Result<Option<Payload>> {
         match self.inner.upgrade() {
-            Some(inner) => inner.request_response(req).await,
+            Some(inner) => inner.request_response(req, None).await,
             None => Err(RSocketError::ConnectionClosed("closed".into()).into()),
         }
     }
@@ -1102,10 +2106,43 @@ impl RSocket for ServerRequester {
     fn request_channel(&self, reqs: Flux<Result<Payload>>) -> Flux<Result<Payload>> {
         use futures::{future, stream};
         match self.inner.upgrade() {
-            Some(inner) => inner.request_channel(reqs),
+            Some(inner) => Box::pin(inner.request_channel_with(reqs, StreamOptions::default())),
             None => Box::pin(stream::once(future::ready(Err(
                 RSocketError::ConnectionClosed("closed".into()).into(),
             )))),
         }
     }
 }
+
+/// ERROR frame terminating a stream whose handler failed, with the code its error maps to.
+fn error_frame(sid: u32, e: &anyhow::Error) -> Frame {
+    let (code, data) = RSocketError::to_stream_error(e);
+    frame::Error::builder(sid, 0)
+        .set_code(code)
+        .set_data(data)
+        .build()
+}
+
+/// Builds the frame of a reassembled payload, after the first of its fragments.
+fn rebuild(sid: u32, flag: u16, first: &Body, joined: Payload) -> Frame {
+    match first {
+        Body::RequestResponse(_) => frame::RequestResponse::builder(sid, flag)
+            .set_all(joined.split())
+            .build(),
+        Body::RequestStream(b) => frame::RequestStream::builder(sid, flag)
+            .set_initial_request_n(b.get_initial_request_n())
+            .set_all(joined.split())
+            .build(),
+        Body::RequestFNF(_) => frame::RequestFNF::builder(sid, flag)
+            .set_all(joined.split())
+            .build(),
+        Body::RequestChannel(b) => frame::RequestChannel::builder(sid, flag)
+            .set_initial_request_n(b.get_initial_request_n())
+            .set_all(joined.split())
+            .build(),
+        Body::Payload(_) => frame::Payload::builder(sid, flag)
+            .set_all(joined.split())
+            .build(),
+        _ => unreachable!(),
+    }
+}
@@ -1,11 +1,14 @@
+use std::collections::HashMap;
 use std::future::Future;
 use std::io::Error as IOError;
 use std::marker::Unpin;
+use std::net::SocketAddr;
 use std::pin::Pin;
 use std::result::Result as StdResult;
 use std::sync::Arc;
 
 use async_trait::async_trait;
+use bytes::Bytes;
 use futures::channel::{mpsc, oneshot};
 use futures::{Sink, Stream};
 use tokio::sync::Notify;
@@ -20,6 +23,108 @@ pub type FrameStream = dyn Stream<Item = StdResult<Frame, RSocketError>> + Send
 
 pub trait Connection {
     fn split(self) -> (Box<FrameSink>, Box<FrameStream>);
+
+    /// Information about the peer, for the transports which know it.
+    fn info(&self) -> Option<ConnectionInfo> {
+        None
+    }
+}
+
+/// Kind of transport carrying a connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransportKind {
+    Tcp,
+    Unix,
+    Tls,
+    WebSocket,
+    Quic,
+    Iroh,
+    Other,
+}
+
+/// Identity the peer has proven to the transport.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PeerIdentity {
+    /// Credentials of the process on the other end of a Unix socket.
+    Unix {
+        uid: u32,
+        gid: u32,
+        pid: Option<i32>,
+    },
+    /// DER encoded certificates presented during a TLS handshake, leaf first.
+    Certificates(Vec<Bytes>),
+    /// Public key of an Iroh node.
+    Node(String),
+}
+
+/// Information about the peer of a connection, as known to its transport.
+///
+/// It is available to acceptors through `SetupPayload::connection_info`, and to responders
+/// through `RequestContext::connection_info`.
+#[derive(Debug, Clone)]
+pub struct ConnectionInfo {
+    kind: TransportKind,
+    local_addr: Option<SocketAddr>,
+    remote_addr: Option<SocketAddr>,
+    identity: Option<PeerIdentity>,
+    attributes: HashMap<String, String>,
+}
+
+impl ConnectionInfo {
+    pub fn new(kind: TransportKind) -> ConnectionInfo {
+        ConnectionInfo {
+            kind,
+            local_addr: None,
+            remote_addr: None,
+            identity: None,
+            attributes: HashMap::new(),
+        }
+    }
+
+    pub fn set_local_addr(mut self, addr: Option<SocketAddr>) -> Self {
+        self.local_addr = addr;
+        self
+    }
+
+    pub fn set_remote_addr(mut self, addr: Option<SocketAddr>) -> Self {
+        self.remote_addr = addr;
+        self
+    }
+
+    pub fn set_identity(mut self, identity: Option<PeerIdentity>) -> Self {
+        self.identity = identity;
+        self
+    }
+
+    /// Adds a transport specific attribute, such as a header of a WebSocket upgrade request.
+    pub fn set_attribute(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.attributes.insert(key.into(), value.into());
+        self
+    }
+
+    pub fn kind(&self) -> TransportKind {
+        self.kind
+    }
+
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.local_addr
+    }
+
+    pub fn remote_addr(&self) -> Option<SocketAddr> {
+        self.remote_addr
+    }
+
+    pub fn identity(&self) -> Option<&PeerIdentity> {
+        self.identity.as_ref()
+    }
+
+    pub fn attribute(&self, key: &str) -> Option<&str> {
+        self.attributes.get(key).map(|it| it.as_str())
+    }
+
+    pub fn attributes(&self) -> &HashMap<String, String> {
+        &self.attributes
+    }
 }
 
 #[async_trait]
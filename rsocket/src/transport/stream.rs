@@ -0,0 +1,183 @@
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use tokio::sync::watch;
+
+/// Interaction model of a request.
+///
+/// Fire and forget and metadata push do not open a stream which could be tracked, they only
+/// show up in the `RequestContext` of their handlers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InteractionType {
+    RequestResponse,
+    RequestStream,
+    RequestChannel,
+    FireAndForget,
+    MetadataPush,
+}
+
+/// Lifecycle state of a stream.
+///
+/// Only channels carry payloads in both directions, so only they get half-closed: a request
+/// response or a request stream terminates as soon as its responder completes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamState {
+    /// The request has been sent, nothing came back from the responder yet.
+    Requested,
+    /// Payloads may flow in both directions.
+    Active,
+    /// We completed our side, the peer may still send payloads.
+    HalfClosedLocal,
+    /// The peer completed its side, we may still send payloads.
+    HalfClosedRemote,
+    /// Both sides are done, the stream is about to be released.
+    Terminated,
+}
+
+/// Snapshot of a stream in flight on a connection.
+#[derive(Debug, Clone)]
+pub struct StreamInfo {
+    pub stream_id: u32,
+    pub interaction: InteractionType,
+    pub state: StreamState,
+    /// Whether the stream was opened by this side of the connection.
+    pub requester: bool,
+    /// Time elapsed since the stream was opened.
+    pub age: Duration,
+}
+
+#[derive(Debug)]
+struct Entry {
+    interaction: InteractionType,
+    state: StreamState,
+    requester: bool,
+    opened: Instant,
+    // signals the cancellation of the stream to its responder
+    cancel: (watch::Sender<bool>, watch::Receiver<bool>),
+}
+
+/// Lifecycle of the streams of a connection, the source of truth for whether a stream is alive.
+#[derive(Debug, Default)]
+pub(crate) struct Streams {
+    entries: DashMap<u32, Entry>,
+}
+
+impl Streams {
+    /// Registers a stream, either sent by us or received from the peer.
+    pub(crate) fn open(&self, sid: u32, interaction: InteractionType, requester: bool) {
+        let state = if requester {
+            StreamState::Requested
+        } else {
+            StreamState::Active
+        };
+        self.entries.insert(
+            sid,
+            Entry {
+                interaction,
+                state,
+                requester,
+                opened: Instant::now(),
+                cancel: watch::channel(false),
+            },
+        );
+    }
+
+    /// Marks that the responder has answered a stream we requested.
+    pub(crate) fn activate(&self, sid: u32) {
+        if let Some(mut it) = self.entries.get_mut(&sid) {
+            if it.state == StreamState::Requested {
+                it.state = StreamState::Active;
+            }
+        }
+    }
+
+    /// Completes one side of a stream, returns true once the stream has terminated.
+    pub(crate) fn close(&self, sid: u32, local: bool) -> bool {
+        let mut entry = match self.entries.get_mut(&sid) {
+            Some(it) => it,
+            None => return false,
+        };
+        // without a channel, completion of the responder ends the stream.
+        let terminated = if entry.interaction != InteractionType::RequestChannel {
+            entry.requester != local
+        } else {
+            matches!(
+                (entry.state, local),
+                (StreamState::HalfClosedRemote, true) | (StreamState::HalfClosedLocal, false)
+            )
+        };
+        entry.state = match (terminated, local) {
+            (true, _) => StreamState::Terminated,
+            (false, true) => StreamState::HalfClosedLocal,
+            (false, false) => StreamState::HalfClosedRemote,
+        };
+        terminated
+    }
+
+    /// Receives the cancellation of a stream, which never happens for unknown streams.
+    pub(crate) fn cancellation(&self, sid: u32) -> watch::Receiver<bool> {
+        match self.entries.get(&sid) {
+            Some(it) => it.cancel.1.clone(),
+            None => watch::channel(false).1,
+        }
+    }
+
+    /// Signals that a stream is cancelled before it could complete.
+    pub(crate) fn cancel(&self, sid: u32) {
+        if let Some(it) = self.entries.get(&sid) {
+            let _ = it.cancel.0.send(true);
+        }
+    }
+
+    pub(crate) fn cancel_all(&self) {
+        for it in self.entries.iter() {
+            let _ = it.cancel.0.send(true);
+        }
+    }
+
+    pub(crate) fn interaction(&self, sid: u32) -> Option<InteractionType> {
+        self.entries.get(&sid).map(|it| it.interaction)
+    }
+
+    pub(crate) fn remove(&self, sid: u32) {
+        self.entries.remove(&sid);
+    }
+
+    /// Whether the stream was opened by this side of the connection.
+    pub(crate) fn is_requester(&self, sid: u32) -> bool {
+        self.entries.get(&sid).is_some_and(|it| it.requester)
+    }
+
+    pub(crate) fn contains(&self, sid: u32) -> bool {
+        self.entries.contains_key(&sid)
+    }
+
+    /// Number of streams alive, whichever side opened them.
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub(crate) fn clear(&self) {
+        self.entries.clear();
+    }
+
+    pub(crate) fn snapshot(&self) -> Vec<StreamInfo> {
+        let mut streams: Vec<StreamInfo> = self
+            .entries
+            .iter()
+            .map(|it| StreamInfo {
+                stream_id: *it.key(),
+                interaction: it.interaction,
+                state: it.state,
+                requester: it.requester,
+                age: it.opened.elapsed(),
+            })
+            .collect();
+        streams.sort_by_key(|it| it.stream_id);
+        streams
+    }
+}
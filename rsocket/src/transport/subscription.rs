@@ -0,0 +1,304 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures::Stream;
+use tokio::sync::mpsc;
+use tokio::time::Sleep;
+
+use super::misc::Credit;
+use super::outbound::Outbound;
+use crate::error::RSocketError;
+use crate::frame::{self, Frame, REQUEST_MAX};
+use crate::payload::Payload;
+use crate::Result;
+
+/// Demand policy of an inbound stream.
+///
+/// # Example
+/// ```
+/// use rsocket_rust::prelude::*;
+///
+/// // ask for 10 elements up front and never replenish automatically.
+/// let opts = StreamOptions::new().initial_request_n(10).limit_rate(0);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct StreamOptions {
+    initial_request_n: Option<u32>,
+    limit_rate: u32,
+    timeout: Option<Duration>,
+    weight: u32,
+}
+
+impl Default for StreamOptions {
+    fn default() -> StreamOptions {
+        StreamOptions {
+            initial_request_n: None,
+            limit_rate: 32,
+            timeout: None,
+            weight: 1,
+        }
+    }
+}
+
+impl StreamOptions {
+    pub fn new() -> StreamOptions {
+        StreamOptions::default()
+    }
+
+    /// Sets the demand sent along with the request frame, defaults to the limit rate.
+    pub fn initial_request_n(mut self, n: u32) -> Self {
+        self.initial_request_n = Some(n.min(REQUEST_MAX));
+        self
+    }
+
+    /// Sets the amount of elements to prefetch.
+    ///
+    /// Demand is replenished with REQUEST_N each time three quarters of it have been consumed.
+    /// Zero disables automatic replenishment, leaving it to `Subscription::request`.
+    pub fn limit_rate(mut self, n: u32) -> Self {
+        self.limit_rate = n.min(REQUEST_MAX);
+        self
+    }
+
+    /// Sets the time the whole stream may take, it gets cancelled and fails with
+    /// `RSocketError::Timeout` once elapsed.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the frames the stream may write in a row while sharing the connection with others.
+    ///
+    /// It matters for the payloads of a channel, see `RequestContext::set_weight` for the
+    /// responder side.
+    pub fn weight(mut self, weight: u32) -> Self {
+        self.weight = weight.max(1);
+        self
+    }
+
+    pub fn get_initial_request_n(&self) -> u32 {
+        self.initial_request_n.unwrap_or(self.limit_rate)
+    }
+
+    pub fn get_limit_rate(&self) -> u32 {
+        self.limit_rate
+    }
+
+    pub fn get_timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    pub fn get_weight(&self) -> u32 {
+        self.weight
+    }
+
+    fn replenish_threshold(&self) -> u32 {
+        match self.limit_rate {
+            0 => 0,
+            n if n >= REQUEST_MAX => 0,
+            n => (n - (n >> 2)).max(1),
+        }
+    }
+}
+
+/// Outcome of handing an inbound element to its subscriber.
+pub(crate) enum Delivery {
+    Delivered,
+    /// The peer sent more elements than have been requested.
+    Unrequested,
+    /// The subscriber is gone.
+    Closed,
+}
+
+/// Delivering end of an inbound stream, which admits as many elements as have been requested.
+#[derive(Debug, Clone)]
+pub(crate) struct Inbound {
+    tx: mpsc::UnboundedSender<Result<Payload>>,
+    demand: Arc<Credit>,
+}
+
+/// Receiving end of an inbound stream.
+#[derive(Debug)]
+pub(crate) struct InboundReceiver {
+    rx: mpsc::UnboundedReceiver<Result<Payload>>,
+    demand: Arc<Credit>,
+}
+
+impl Inbound {
+    /// Creates both ends of an inbound stream, `n` elements being requested along with the
+    /// request frame.
+    pub(crate) fn channel(n: u32) -> (Inbound, InboundReceiver) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let demand = Arc::new(Credit::new(n));
+        let receiver = InboundReceiver {
+            rx,
+            demand: demand.clone(),
+        };
+        (Inbound { tx, demand }, receiver)
+    }
+
+    /// Hands a PAYLOAD to the subscriber without waiting, as long as it has been requested.
+    pub(crate) fn deliver(&self, payload: Payload) -> Delivery {
+        if self.tx.is_closed() {
+            Delivery::Closed
+        } else if !self.demand.try_acquire() {
+            Delivery::Unrequested
+        } else if self.tx.send(Ok(payload)).is_err() {
+            Delivery::Closed
+        } else {
+            Delivery::Delivered
+        }
+    }
+
+    /// Hands an element to the subscriber regardless of the demand, returns false if it is gone.
+    ///
+    /// It is meant for errors and the payload coming along with REQUEST_CHANNEL.
+    pub(crate) fn send(&self, item: Result<Payload>) -> bool {
+        self.tx.send(item).is_ok()
+    }
+}
+
+/// Handle of an inbound stream which controls the demand signalled to the peer.
+///
+/// Elements are buffered locally up to the demand in flight, the peer gets cancelled if it
+/// sends more than has been requested.
+pub struct Subscription {
+    /// None for a subscription failing before its request has been sent
+    sid: Option<u32>,
+    tx: Outbound,
+    receiver: InboundReceiver,
+    threshold: u32,
+    consumed: u32,
+    canceller: Option<Box<dyn FnOnce() -> Result<()> + Send>>,
+    deadline: Option<(Pin<Box<Sleep>>, Duration)>,
+    expired: bool,
+}
+
+impl Subscription {
+    pub(crate) fn new(
+        sid: u32,
+        tx: Outbound,
+        receiver: InboundReceiver,
+        opts: &StreamOptions,
+    ) -> Subscription {
+        Subscription {
+            sid: Some(sid),
+            tx,
+            receiver,
+            threshold: opts.replenish_threshold(),
+            consumed: 0,
+            canceller: None,
+            deadline: None,
+            expired: false,
+        }
+    }
+
+    /// A subscription failing right away, without anything sent to the peer.
+    ///
+    /// It has no stream, requesting or cancelling it sends nothing.
+    pub(crate) fn failed(tx: Outbound, e: anyhow::Error) -> Subscription {
+        let (sender, receiver) = Inbound::channel(0);
+        sender.send(Err(e));
+        let mut sub = Subscription::new(0, tx, receiver, &StreamOptions::default());
+        sub.sid = None;
+        sub
+    }
+
+    /// Sets what cancels the stream, telling the peer and releasing the local state.
+    pub(crate) fn on_cancel(mut self, canceller: Box<dyn FnOnce() -> Result<()> + Send>) -> Self {
+        self.canceller = Some(canceller);
+        self
+    }
+
+    /// Cancels the stream and fails it once `timeout` has elapsed.
+    pub(crate) fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.deadline = timeout.map(|it| (Box::pin(tokio::time::sleep(it)), it));
+        self
+    }
+
+    /// Id of the stream, 0 if the request failed before it got one.
+    pub fn stream_id(&self) -> u32 {
+        self.sid.unwrap_or(0)
+    }
+
+    /// Requests `n` more elements from the peer.
+    pub fn request(&self, n: u32) -> Result<()> {
+        let sid = match self.sid {
+            Some(it) if n > 0 => it,
+            _ => return Ok(()),
+        };
+        // granted before the peer hears of it, so the payloads it answers with are admitted.
+        self.receiver.demand.add(n);
+        let sending = frame::RequestN::builder(sid, 0)
+            .set_n(n.min(REQUEST_MAX))
+            .build();
+        self.tx
+            .send(sending)
+            .map_err(|_| RSocketError::ConnectionClosed("closed".into()).into())
+    }
+
+    /// Cancels the stream, elements which are already buffered are discarded.
+    pub fn cancel(&mut self) -> Result<()> {
+        self.receiver.rx.close();
+        let canceller = match self.canceller.take() {
+            Some(it) => it,
+            // already cancelled
+            None => return Ok(()),
+        };
+        canceller()
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        // nobody is left to consume the stream, stop the peer from producing it.
+        if let Err(e) = self.cancel() {
+            debug!("cancel stream {} on drop failed: {}", self.stream_id(), e);
+        }
+    }
+}
+
+impl Stream for Subscription {
+    type Item = Result<Payload>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.expired {
+            return Poll::Ready(None);
+        }
+        if let Some((sleep, timeout)) = &mut self.deadline {
+            if sleep.as_mut().poll(cx).is_ready() {
+                let timeout = *timeout;
+                self.deadline = None;
+                self.expired = true;
+                if let Err(e) = self.cancel() {
+                    debug!("cancel stream {} failed: {}", self.stream_id(), e);
+                }
+                return Poll::Ready(Some(Err(RSocketError::Timeout(timeout).into())));
+            }
+        }
+        if self.threshold > 0 && self.consumed >= self.threshold {
+            let n = self.consumed;
+            self.consumed = 0;
+            if let Err(e) = self.request(n) {
+                debug!("replenish stream {} failed: {}", self.stream_id(), e);
+            }
+        }
+        match self.receiver.rx.poll_recv(cx) {
+            Poll::Ready(Some(it)) => {
+                if self.threshold > 0 {
+                    self.consumed += 1;
+                }
+                Poll::Ready(Some(it))
+            }
+            Poll::Ready(None) => {
+                self.canceller = None;
+                self.deadline = None;
+                Poll::Ready(None)
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
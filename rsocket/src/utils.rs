@@ -1,15 +1,20 @@
+use std::collections::VecDeque;
 use std::error::Error;
 use std::future::Future;
+use std::io::{self, IoSlice};
 use std::pin::Pin;
+use std::task::{Context, Poll};
 
 use async_stream::stream;
 use async_trait::async_trait;
 use bytes::{Buf, BufMut, Bytes, BytesMut};
-use futures::{pin_mut, FutureExt, Sink, SinkExt, Stream, StreamExt};
+use futures::{pin_mut, ready, FutureExt, Sink, SinkExt, Stream, StreamExt};
+use tokio::io::AsyncWrite;
 use tokio::sync::mpsc;
 
 use super::spi::{Flux, RSocket};
 use crate::error::RSocketError;
+use crate::frame::Frame;
 use crate::payload::Payload;
 use crate::runtime;
 use crate::Result;
@@ -188,3 +193,103 @@ impl u24 {
         Self::parse(&raw)
     }
 }
+
+const LEN_BYTES: usize = 3;
+// payloads smaller than this get copied rather than written as a slice of their own.
+const MIN_CHUNK_SIZE: usize = 512;
+// bytes buffered before sending waits for them to be written.
+const MAX_BUFFERED: usize = 256 * 1024;
+const MAX_IO_SLICES: usize = 64;
+
+/// Writes frames prefixed with their length, as stream transports frame them, without copying
+/// the data and metadata they carry.
+///
+/// Frames are buffered until flushed, then written together with vectored writes.
+pub struct LengthBasedFrameWriter<W> {
+    io: W,
+    // fields of the frames not written yet, along with their small payloads.
+    head: BytesMut,
+    chunks: VecDeque<Bytes>,
+    buffered: usize,
+}
+
+impl<W> LengthBasedFrameWriter<W> {
+    pub fn new(io: W) -> LengthBasedFrameWriter<W> {
+        LengthBasedFrameWriter {
+            io,
+            head: BytesMut::new(),
+            chunks: VecDeque::new(),
+            buffered: 0,
+        }
+    }
+}
+
+impl<W: AsyncWrite + Unpin> LengthBasedFrameWriter<W> {
+    fn poll_write_buffered(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        if !self.head.is_empty() {
+            self.chunks.push_back(self.head.split().freeze());
+        }
+        while !self.chunks.is_empty() {
+            let slices: Vec<IoSlice<'_>> = self
+                .chunks
+                .iter()
+                .take(MAX_IO_SLICES)
+                .map(|it| IoSlice::new(it))
+                .collect();
+            let mut n = ready!(Pin::new(&mut self.io).poll_write_vectored(cx, &slices))?;
+            if n == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+            self.buffered -= n;
+            while n > 0 {
+                let first = self.chunks.front_mut().unwrap();
+                if n < first.len() {
+                    first.advance(n);
+                    break;
+                }
+                n -= first.len();
+                self.chunks.pop_front();
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<W: AsyncWrite + Unpin> Sink<Frame> for LengthBasedFrameWriter<W> {
+    type Error = io::Error;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        if self.buffered < MAX_BUFFERED {
+            return Poll::Ready(Ok(()));
+        }
+        self.poll_write_buffered(cx)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: Frame) -> io::Result<()> {
+        let this = &mut *self;
+        let l = item.len();
+        this.buffered += LEN_BYTES + l;
+        u24::from(l).write_to(&mut this.head);
+        for it in item.write_head_to(&mut this.head).iter().flatten() {
+            if it.len() < MIN_CHUNK_SIZE {
+                this.head.put_slice(it);
+            } else {
+                if !this.head.is_empty() {
+                    this.chunks.push_back(this.head.split().freeze());
+                }
+                this.chunks.push_back(it.clone());
+            }
+        }
+        Ok(())
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        ready!(self.poll_write_buffered(cx))?;
+        Pin::new(&mut self.io).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        ready!(self.as_mut().poll_flush(cx))?;
+        Pin::new(&mut self.io).poll_shutdown(cx)
+    }
+}